@@ -0,0 +1,426 @@
+mod output;
+
+use chrono::NaiveDate;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use entity::{
+    locations::{self, Entity as Locations},
+    residents::{self, Entity as Residents},
+    sea_orm_active_enums::LocationKind,
+    timestamps::{self, Entity as Timestamps},
+};
+use output::{message, print, print_csv};
+use scan_mvcf::{
+    app_config::DB,
+    models::badges::{self, canonical},
+    models::consistency::{find_discrepancies, repair, Discrepancy, Trust},
+    models::residents::{conflict, normalize_doc, save_resident},
+    models::webhooks,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, ModelTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use serde::Serialize;
+
+type AdminResult = Result<(), Box<dyn std::error::Error>>;
+
+/// Manage residents, locations and badges for the MVCF scanner
+#[derive(Parser)]
+#[command(name = "mvscan-admin", version)]
+struct Cli {
+    /// print JSON instead of tables
+    #[arg(long, global = true)]
+    json: bool,
+    /// defaults to DATABASE_URL from the environment or .env
+    #[arg(long, env = "DATABASE_URL", hide_env_values = true)]
+    database_url: String,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List, add, update and release residents
+    #[command(subcommand)]
+    Residents(ResidentCommand),
+    /// List and add locations
+    #[command(subcommand)]
+    Locations(LocationCommand),
+    /// Badge (RFID) assignments
+    #[command(subcommand)]
+    Badges(BadgeCommand),
+    /// Write residents, locations or timestamps to stdout
+    Export(ExportArgs),
+    /// Reset current_location to each resident's latest timestamp, logging the corrections
+    Recompute {
+        /// only report what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ResidentCommand {
+    List {
+        #[arg(long)]
+        location: Option<i32>,
+        #[arg(long)]
+        unit: Option<i32>,
+    },
+    Show {
+        rfid: String,
+    },
+    Add {
+        #[arg(long)]
+        rfid: String,
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        doc: String,
+        #[arg(long)]
+        room: String,
+        #[arg(long)]
+        unit: i32,
+        #[arg(long)]
+        level: i32,
+        /// defaults to the resident's unit
+        #[arg(long)]
+        location: Option<i32>,
+    },
+    Update {
+        rfid: String,
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        doc: Option<String>,
+        #[arg(long)]
+        room: Option<String>,
+        #[arg(long)]
+        unit: Option<i32>,
+        #[arg(long)]
+        level: Option<i32>,
+    },
+    /// Remove a resident from the roster
+    Release {
+        rfid: String,
+        /// also delete their scan history, required if they have any
+        #[arg(long)]
+        purge_history: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum LocationCommand {
+    List,
+    Add {
+        #[arg(long)]
+        id: i32,
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        level: i32,
+        #[arg(long)]
+        parent: Option<i32>,
+        /// building, unit, pod, program_area or external
+        #[arg(long, value_parser = parse_kind)]
+        kind: Option<LocationKind>,
+        #[arg(long)]
+        capacity: Option<i32>,
+    },
+}
+
+#[derive(Subcommand)]
+enum BadgeCommand {
+    /// Move a resident from one badge to another
+    Reassign { old_rfid: String, new_rfid: String },
+    /// Show the badge id raw reader output is stored as
+    Decode { raw: String },
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    what: ExportKind,
+    #[arg(long, value_enum, default_value = "csv")]
+    format: ExportFormat,
+    /// timestamps on or after this date
+    #[arg(long)]
+    from: Option<NaiveDate>,
+    /// timestamps on or before this date
+    #[arg(long)]
+    to: Option<NaiveDate>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportKind {
+    Residents,
+    Locations,
+    Timestamps,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Csv,
+    Json,
+}
+
+fn parse_kind(kind: &str) -> Result<LocationKind, String> {
+    serde_json::from_value(serde_json::Value::String(kind.to_string()))
+        .map_err(|_| format!("unknown location kind: {}", kind))
+}
+
+#[tokio::main]
+async fn main() {
+    dotenvy::dotenv().ok();
+    let cli = Cli::parse();
+    // the schema is left to the migration CLI, so an admin can fix the data a
+    // failed migration waits on
+    let result = match DB::open(&cli.database_url).await {
+        Ok(db) => run(&db.0, cli.command, cli.json).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(db: &DatabaseConnection, command: Command, json: bool) -> AdminResult {
+    match command {
+        Command::Residents(command) => residents(db, command, json).await,
+        Command::Locations(command) => locations(db, command, json).await,
+        Command::Badges(BadgeCommand::Reassign { old_rfid, new_rfid }) => {
+            let new_rfid = canonical(&new_rfid)?;
+            if find_resident(db, &new_rfid).await.is_ok() {
+                return Err(format!("badge {} is already assigned", new_rfid).into());
+            }
+            let mut resident = find_resident(db, &old_rfid).await?.into_active_model();
+            resident.rfid = Set(new_rfid);
+            print(&[save_resident(db, resident).await??], json);
+            Ok(())
+        }
+        Command::Badges(BadgeCommand::Decode { raw }) => {
+            message(&canonical(&raw)?, json);
+            Ok(())
+        }
+        Command::Export(args) => export(db, args).await,
+        Command::Recompute { dry_run } => recompute(db, dry_run, json).await,
+    }
+}
+
+async fn find_resident(
+    db: &DatabaseConnection,
+    rfid: &str,
+) -> Result<residents::Model, Box<dyn std::error::Error>> {
+    // badges the canonical badge migration couldn't convert are still stored
+    // as first typed, and only reachable that way
+    let stored = Residents::find()
+        .filter(residents::Column::Rfid.eq(rfid.trim()))
+        .one(db)
+        .await?;
+    if let Some(resident) = stored {
+        return Ok(resident);
+    }
+    Residents::find()
+        .filter(badges::matches(rfid))
+        .one(db)
+        .await?
+        .ok_or_else(|| format!("no resident with badge {}", rfid).into())
+}
+
+async fn residents(db: &DatabaseConnection, command: ResidentCommand, json: bool) -> AdminResult {
+    match command {
+        ResidentCommand::List { location, unit } => {
+            let mut query = Residents::find().order_by_asc(residents::Column::Name);
+            if let Some(location) = location {
+                query = query.filter(residents::Column::CurrentLocation.eq(location));
+            }
+            if let Some(unit) = unit {
+                query = query.filter(residents::Column::Unit.eq(unit));
+            }
+            print(&query.all(db).await?, json);
+        }
+        ResidentCommand::Show { rfid } => print(&[find_resident(db, &rfid).await?], json),
+        ResidentCommand::Add {
+            rfid,
+            name,
+            doc,
+            room,
+            unit,
+            level,
+            location,
+        } => {
+            let doc = normalize_doc(&doc);
+            let resident = residents::ActiveModel {
+                rfid: Set(canonical(&rfid)?),
+                name: Set(name),
+                doc: Set(doc.clone()),
+                room: Set(room),
+                unit: Set(unit),
+                level: Set(level),
+                current_location: Set(location.unwrap_or(unit)),
+                ..Default::default()
+            };
+            let txn = db.begin().await?;
+            // the same message the API gives for a badge or DOC number that's taken
+            let resident = match resident.insert(&txn).await {
+                Ok(resident) => resident,
+                Err(err) => return Err(conflict(&err, &doc).map_or_else(|| err.into(), Into::into)),
+            };
+            webhooks::enqueue(&txn, webhooks::RESIDENT_CREATED, &resident).await?;
+            txn.commit().await?;
+            print(&[resident], json);
+        }
+        ResidentCommand::Update {
+            rfid,
+            name,
+            doc,
+            room,
+            unit,
+            level,
+        } => {
+            let mut resident = find_resident(db, &rfid).await?.into_active_model();
+            if let Some(name) = name {
+                resident.name = Set(name);
+            }
+            if let Some(doc) = doc {
+                resident.doc = Set(normalize_doc(&doc));
+            }
+            if let Some(room) = room {
+                resident.room = Set(room);
+            }
+            if let Some(unit) = unit {
+                resident.unit = Set(unit);
+            }
+            if let Some(level) = level {
+                resident.level = Set(level);
+            }
+            print(&[save_resident(db, resident).await??], json);
+        }
+        ResidentCommand::Release {
+            rfid,
+            purge_history,
+        } => {
+            let resident = find_resident(db, &rfid).await?;
+            let history = Timestamps::find()
+                .filter(timestamps::Column::Rfid.eq(resident.id))
+                .count(db)
+                .await?;
+            if history > 0 && !purge_history {
+                return Err(format!(
+                    "{} has {} timestamps, pass --purge-history to delete them as well",
+                    resident.name, history
+                )
+                .into());
+            }
+            let txn = db.begin().await?;
+            Timestamps::delete_many()
+                .filter(timestamps::Column::Rfid.eq(resident.id))
+                .exec(&txn)
+                .await?;
+            let name = resident.name.clone();
+            webhooks::enqueue(&txn, webhooks::RESIDENT_RELEASED, &resident).await?;
+            resident.delete(&txn).await?;
+            txn.commit().await?;
+            message(&format!("Released {} ({})", name, rfid), json);
+        }
+    }
+    Ok(())
+}
+
+async fn locations(db: &DatabaseConnection, command: LocationCommand, json: bool) -> AdminResult {
+    match command {
+        LocationCommand::List => {
+            print(
+                &Locations::find()
+                    .order_by_asc(locations::Column::Id)
+                    .all(db)
+                    .await?,
+                json,
+            );
+        }
+        LocationCommand::Add {
+            id,
+            name,
+            level,
+            parent,
+            kind,
+            capacity,
+        } => {
+            let location = locations::ActiveModel {
+                id: Set(id),
+                name: Set(name),
+                level: Set(level),
+                parent_id: Set(parent),
+                kind: Set(kind.unwrap_or_default()),
+                capacity: Set(capacity),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+            print(&[location], json);
+        }
+    }
+    Ok(())
+}
+
+async fn export(db: &DatabaseConnection, args: ExportArgs) -> AdminResult {
+    match args.what {
+        ExportKind::Residents => {
+            let residents = Residents::find()
+                .order_by_asc(residents::Column::Id)
+                .all(db)
+                .await?;
+            write_export(&residents, args.format);
+        }
+        ExportKind::Locations => {
+            let locations = Locations::find()
+                .order_by_asc(locations::Column::Id)
+                .all(db)
+                .await?;
+            write_export(&locations, args.format);
+        }
+        ExportKind::Timestamps => {
+            let mut query = Timestamps::find().order_by_asc(timestamps::Column::Ts);
+            if let Some(from) = args.from {
+                query = query.filter(timestamps::Column::Ts.gte(from.and_hms_opt(0, 0, 0)));
+            }
+            if let Some(to) = args.to {
+                query = query.filter(timestamps::Column::Ts.lte(to.and_hms_opt(23, 59, 59)));
+            }
+            write_export(&query.all(db).await?, args.format);
+        }
+    }
+    Ok(())
+}
+
+fn write_export<T: output::Tabular + Serialize>(items: &[T], format: ExportFormat) {
+    match format {
+        ExportFormat::Csv => print_csv(items),
+        ExportFormat::Json => print(items, true),
+    }
+}
+
+impl output::Tabular for Discrepancy {
+    fn headers() -> Vec<&'static str> {
+        vec!["rfid", "name", "stored", "replayed", "last scan"]
+    }
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.rfid.clone(),
+            self.name.clone(),
+            self.stored_location.to_string(),
+            self.replayed_location.to_string(),
+            self.last_scan.format("%Y-%m-%d %H:%M:%S").to_string(),
+        ]
+    }
+}
+
+async fn recompute(db: &DatabaseConnection, dry_run: bool, json: bool) -> AdminResult {
+    let found = if dry_run {
+        find_discrepancies(db, None).await?
+    } else {
+        repair(db, Trust::History, None).await?
+    };
+    print(&found, json);
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use entity::{locations, residents, timestamps};
+use serde::Serialize;
+
+/// Something that can be printed as a row of a plain text table or CSV
+pub trait Tabular {
+    fn headers() -> Vec<&'static str>;
+    fn row(&self) -> Vec<String>;
+}
+
+impl Tabular for residents::Model {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "id", "rfid", "name", "doc", "room", "unit", "level", "location",
+        ]
+    }
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.rfid.clone(),
+            self.name.clone(),
+            self.doc.clone(),
+            self.room.clone(),
+            self.unit.to_string(),
+            self.level.to_string(),
+            self.current_location.to_string(),
+        ]
+    }
+}
+
+impl Tabular for locations::Model {
+    fn headers() -> Vec<&'static str> {
+        vec!["id", "name", "level", "kind", "parent", "capacity"]
+    }
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.name.clone(),
+            self.level.to_string(),
+            serde_json::to_value(self.kind)
+                .ok()
+                .and_then(|kind| kind.as_str().map(str::to_string))
+                .unwrap_or_default(),
+            optional(self.parent_id),
+            optional(self.capacity),
+        ]
+    }
+}
+
+impl Tabular for timestamps::Model {
+    fn headers() -> Vec<&'static str> {
+        vec!["id", "resident", "location", "ts", "received_at", "scanner"]
+    }
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.rfid.to_string(),
+            self.location.to_string(),
+            self.ts.format("%Y-%m-%d %H:%M:%S").to_string(),
+            self.received_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            self.scanner.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+fn optional(value: Option<i32>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Print `items` as pretty JSON or as an aligned table
+pub fn print<T: Tabular + Serialize>(items: &[T], json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(items).unwrap());
+        return;
+    }
+    let headers = T::headers();
+    let rows: Vec<Vec<String>> = items.iter().map(Tabular::row).collect();
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: Vec<String>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    println!(
+        "{}",
+        line(headers.iter().map(|h| h.to_uppercase()).collect())
+    );
+    for row in rows {
+        println!("{}", line(row));
+    }
+}
+
+/// Print `items` as CSV with a header row
+pub fn print_csv<T: Tabular>(items: &[T]) {
+    println!("{}", T::headers().join(","));
+    for item in items {
+        let cells: Vec<String> = item.row().iter().map(|cell| csv_cell(cell)).collect();
+        println!("{}", cells.join(","));
+    }
+}
+
+fn csv_cell(cell: &str) -> String {
+    if cell.contains([',', '"', '\n']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// Print a one line result, or `{"message": ...}` for --json
+pub fn message(text: &str, json: bool) {
+    if json {
+        println!("{}", serde_json::json!({ "message": text }));
+    } else {
+        println!("{}", text);
+    }
+}
@@ -0,0 +1,94 @@
+use crate::frames::{self, FrameError};
+use std::fmt::{Display, Formatter};
+
+/// Why raw reader output isn't a badge id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BadgeError {
+    Empty,
+    Malformed(String),
+    Parity(String),
+    Checksum(String),
+}
+
+impl Display for BadgeError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            BadgeError::Empty => write!(f, "Badge id is empty"),
+            BadgeError::Malformed(raw) => write!(f, "Unrecognized badge format: {}", raw),
+            BadgeError::Parity(raw) => write!(f, "Wiegand parity check failed for badge {}", raw),
+            BadgeError::Checksum(raw) => write!(f, "EM4100 checksum mismatch for badge {}", raw),
+        }
+    }
+}
+
+impl std::error::Error for BadgeError {}
+
+/// Normalize what a reader sent, or someone typed, into the badge id residents
+/// are stored under: decimal digits without leading zeros. Accepts
+///
+/// - decimal, e.g. `0033752069`
+/// - hex with a `0x` prefix, or bare when it has a letter in it, as wedge
+///   readers type some cards
+/// - facility code and card number, e.g. `18,12345` or `18:12345`
+/// - raw Wiegand bits as hex with their parity, `W26:246073` or `W34:...`
+/// - EM4100 data and checksum as hex, `EM:010203040501`
+///
+/// Facility/card, Wiegand and the low 32 bits of EM4100 data all come out as
+/// the card data read as one number, the same digits a keyboard-wedge reader
+/// types for that card.
+pub fn canonical(raw: &str) -> Result<String, BadgeError> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Err(BadgeError::Empty);
+    }
+    let upper = raw.to_ascii_uppercase();
+    // frames from readers are decoded with the same functions
+    let value = if let Some(bits) = upper.strip_prefix("W26:") {
+        frames::wiegand(bits, 26).map_err(|err| frame_error(raw, err))?
+    } else if let Some(bits) = upper.strip_prefix("W34:") {
+        frames::wiegand(bits, 34).map_err(|err| frame_error(raw, err))?
+    } else if let Some(data) = upper.strip_prefix("EM:") {
+        frames::em4100(data)
+            .map_err(|err| frame_error(raw, err))?
+            .into()
+    } else if let Some(hex) = upper.strip_prefix("0X") {
+        return hex_value(raw, hex).map(|value| value.to_string());
+    } else if let Some((facility, card)) = raw.split_once([',', ':', '/']) {
+        facility_card(raw, facility.trim(), card.trim())?
+    } else if raw.bytes().all(|b| b.is_ascii_digit()) {
+        // long decimal ids don't fit any integer, only strip the zeros
+        let digits = raw.trim_start_matches('0');
+        return Ok(if digits.is_empty() { "0" } else { digits }.to_owned());
+    } else if raw.bytes().all(|b| b.is_ascii_hexdigit()) {
+        // all digits reads as decimal above, so only ids with A-F get here
+        return hex_value(raw, &upper).map(|value| value.to_string());
+    } else {
+        return Err(BadgeError::Malformed(raw.to_owned()));
+    };
+    Ok(value.to_string())
+}
+
+fn hex_value(raw: &str, hex: &str) -> Result<u128, BadgeError> {
+    if hex.is_empty() || hex.len() > 32 {
+        return Err(BadgeError::Malformed(raw.to_owned()));
+    }
+    u128::from_str_radix(hex, 16).map_err(|_| BadgeError::Malformed(raw.to_owned()))
+}
+
+fn facility_card(raw: &str, facility: &str, card: &str) -> Result<u64, BadgeError> {
+    let parse = |part: &str| {
+        part.parse::<u64>()
+            .ok()
+            .filter(|value| *value <= u16::MAX.into())
+            .ok_or_else(|| BadgeError::Malformed(raw.to_owned()))
+    };
+    Ok((parse(facility)? << 16) | parse(card)?)
+}
+
+fn frame_error(raw: &str, err: FrameError) -> BadgeError {
+    match err {
+        FrameError::Malformed(_) => BadgeError::Malformed(raw.to_owned()),
+        FrameError::Parity(_) => BadgeError::Parity(raw.to_owned()),
+        FrameError::Checksum(_) => BadgeError::Checksum(raw.to_owned()),
+    }
+}
@@ -0,0 +1,64 @@
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameError {
+    Malformed(String),
+    Parity(String),
+    Checksum(String),
+}
+
+impl Display for FrameError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            FrameError::Malformed(frame) => write!(f, "malformed frame {:?}", frame),
+            FrameError::Parity(frame) => write!(f, "parity check failed on {:?}", frame),
+            FrameError::Checksum(frame) => write!(f, "checksum mismatch on {:?}", frame),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
+fn hex_value(frame: &str) -> Result<u64, FrameError> {
+    let digits = frame
+        .strip_prefix("0x")
+        .or_else(|| frame.strip_prefix("0X"))
+        .unwrap_or(frame);
+    if digits.is_empty() || digits.len() > 16 {
+        return Err(FrameError::Malformed(frame.to_owned()));
+    }
+    u64::from_str_radix(digits, 16).map_err(|_| FrameError::Malformed(frame.to_owned()))
+}
+
+/// The card data between the parity bits of a `bits` long Wiegand frame
+/// given as hex. `W26:`/`W34:` badge ids are decoded with this too.
+pub fn wiegand(frame: &str, bits: u32) -> Result<u64, FrameError> {
+    let value = hex_value(frame)?;
+    if value >> bits != 0 {
+        return Err(FrameError::Malformed(frame.to_owned()));
+    }
+    // the leading parity bit covers the first half, the trailing one the second
+    let half = bits / 2;
+    let leading = value >> half;
+    let trailing = value & ((1 << half) - 1);
+    if !leading.count_ones().is_multiple_of(2) || trailing.count_ones().is_multiple_of(2) {
+        return Err(FrameError::Parity(frame.to_owned()));
+    }
+    Ok((value >> 1) & ((1 << (bits - 2)) - 1))
+}
+
+/// The low 32 bits of card data in an EM4100 frame of 12 hex digits, after
+/// checking its checksum. `EM:` badge ids are decoded with this too.
+pub fn em4100(frame: &str) -> Result<u32, FrameError> {
+    if frame.len() != 12 {
+        return Err(FrameError::Malformed(frame.to_owned()));
+    }
+    let value = hex_value(frame)?;
+    let bytes = value.to_be_bytes();
+    // 2 padding bytes, 5 of card data, then their XOR
+    let checksum = bytes[2..7].iter().fold(0, |acc, byte| acc ^ byte);
+    if checksum != bytes[7] {
+        return Err(FrameError::Checksum(frame.to_owned()));
+    }
+    Ok(u32::from_be_bytes([bytes[3], bytes[4], bytes[5], bytes[6]]))
+}
@@ -0,0 +1,6 @@
+//! Decoding of raw reader output into badge ids, shared by the reader bridge,
+//! the server and the migrations.
+
+pub mod badges;
+
+pub mod frames;
@@ -0,0 +1,70 @@
+use chrono::NaiveDateTime;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Posts scans to the scanner API as one configured location and scanner
+pub struct Poster {
+    pub api: String,
+    pub location: i32,
+    pub scanner: String,
+    pub retries: u32,
+    client: reqwest::blocking::Client,
+}
+
+impl Poster {
+    pub fn new(api: &str, location: i32, scanner: &str) -> Self {
+        Self {
+            api: api.trim_end_matches('/').to_owned(),
+            location,
+            scanner: scanner.to_owned(),
+            retries: 5,
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+
+    /// POST /api/timestamps, retrying with backoff unless the server answers
+    /// 4xx. Every attempt carries the same Idempotency-Key, so a retry after a
+    /// lost response can't toggle the resident twice.
+    pub fn post(&self, rfid: &str, scanned_at: NaiveDateTime) -> Result<Value, reqwest::Error> {
+        let key = format!(
+            "{}-{}-{}",
+            self.scanner,
+            rfid,
+            scanned_at
+                .and_utc()
+                .timestamp_nanos_opt()
+                .unwrap_or_default()
+        );
+        let body = json!({
+            "rfid": rfid,
+            "location": self.location,
+            "scanner": self.scanner,
+            "scanned_at": scanned_at,
+        });
+        let mut attempt = 0;
+        loop {
+            let sent = self
+                .client
+                .post(format!("{}/timestamps", self.api))
+                .header("Idempotency-Key", &key)
+                .json(&body)
+                .timeout(Duration::from_secs(10))
+                .send()
+                .and_then(|response| response.error_for_status());
+            match sent {
+                Ok(response) => return response.json(),
+                // the server turned the scan down, sending it again won't change that
+                Err(err) if err.status().is_some_and(|status| status.is_client_error()) => {
+                    return Err(err)
+                }
+                Err(err) if attempt < self.retries => {
+                    let wait = Duration::from_secs(1 << attempt);
+                    log::warn!("posting {} failed ({}), retrying in {:?}", rfid, err, wait);
+                    std::thread::sleep(wait);
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+pub use mvscan_badges::frames::{em4100, wiegand, FrameError};
+
+const STX: u8 = 0x02;
+const ETX: u8 = 0x03;
+
+/// What the reader sends for one badge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// 26 Wiegand bits as hex: even parity, 8 bit facility, 16 bit card, odd parity
+    Wiegand26,
+    /// 34 Wiegand bits as hex: even parity, 16 bit facility, 16 bit card, odd parity
+    Wiegand34,
+    /// 10 hex digits of card data and 2 of checksum, usually between STX and ETX
+    Em4100,
+    /// the badge number typed out as digits and Enter
+    Wedge,
+}
+
+/// Turn one frame into the badge id to post. Card data from Wiegand and
+/// EM4100 readers comes out as the 10 digit decimal printed on the badge,
+/// the same thing a keyboard-wedge reader types for it.
+pub fn decode(format: Format, frame: &str) -> Result<String, FrameError> {
+    let frame = frame.trim();
+    match format {
+        Format::Wiegand26 => wiegand(frame, 26).map(|data| format!("{:010}", data)),
+        Format::Wiegand34 => wiegand(frame, 34).map(|data| format!("{:010}", data)),
+        Format::Em4100 => em4100(frame).map(|card| format!("{:010}", card)),
+        Format::Wedge => {
+            if frame.is_empty() || !frame.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(FrameError::Malformed(frame.to_owned()));
+            }
+            Ok(frame.to_owned())
+        }
+    }
+}
+
+/// Splits the byte stream from a reader into frames: STX starts one, and ETX,
+/// CR or LF end it
+#[derive(Debug, Default)]
+pub struct Framer {
+    buffer: Vec<u8>,
+}
+
+impl Framer {
+    pub fn push(&mut self, byte: u8) -> Option<String> {
+        match byte {
+            STX => {
+                self.buffer.clear();
+                None
+            }
+            ETX | b'\r' | b'\n' => {
+                let frame = String::from_utf8_lossy(&self.buffer).trim().to_owned();
+                self.buffer.clear();
+                (!frame.is_empty()).then_some(frame)
+            }
+            _ => {
+                self.buffer.push(byte);
+                None
+            }
+        }
+    }
+}
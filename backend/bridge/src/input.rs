@@ -0,0 +1,214 @@
+use crate::frames::Framer;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+/// Where the reader's bytes come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Input {
+    /// a serial port, USB serial adapter or pseudo-terminal
+    Serial,
+    /// a Linux input device (/dev/input/event*), grabbed so keystrokes don't reach other programs
+    Evdev,
+    /// a raw HID keyboard (/dev/hidraw*) sending boot protocol reports
+    Hidraw,
+}
+
+// _IOW('E', 0x90, int)
+const EVIOCGRAB: libc::c_ulong = 0x4004_4590;
+const EV_KEY: u16 = 0x01;
+
+/// Reads frames from an RFID reader
+pub struct Reader {
+    file: File,
+    input: Input,
+    framer: Framer,
+    /// bytes read past the end of the last frame
+    pending: VecDeque<u8>,
+    /// keys held down in the last HID report
+    pressed: Vec<u8>,
+}
+
+impl Reader {
+    pub fn open(path: &Path, input: Input, baud: u32) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NOCTTY)
+            .open(path)?;
+        match input {
+            Input::Serial => configure_serial(&file, baud)?,
+            Input::Evdev => {
+                // SAFETY: EVIOCGRAB takes an int and the fd is open
+                if unsafe { libc::ioctl(file.as_raw_fd(), EVIOCGRAB, 1 as libc::c_int) } < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Input::Hidraw => {}
+        }
+        Ok(Self {
+            file,
+            input,
+            framer: Framer::default(),
+            pending: VecDeque::new(),
+            pressed: Vec::new(),
+        })
+    }
+
+    /// Blocks until the next complete frame. `None` once the device is gone.
+    pub fn next_frame(&mut self) -> io::Result<Option<String>> {
+        loop {
+            while let Some(byte) = self.pending.pop_front() {
+                if let Some(frame) = self.framer.push(byte) {
+                    return Ok(Some(frame));
+                }
+            }
+            let bytes = match self.input {
+                Input::Serial => self.read_serial()?,
+                Input::Evdev => self.read_evdev()?,
+                Input::Hidraw => self.read_hidraw()?,
+            };
+            let Some(bytes) = bytes else { return Ok(None) };
+            self.pending.extend(bytes);
+        }
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<Option<usize>> {
+        match self.file.read(buf) {
+            Ok(0) => Ok(None),
+            Ok(read) => Ok(Some(read)),
+            // a pseudo-terminal reports EIO once the other side hangs up
+            Err(err) if err.raw_os_error() == Some(libc::EIO) => Ok(None),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => Ok(Some(0)),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn read_serial(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut buf = [0u8; 64];
+        Ok(self.read(&mut buf)?.map(|read| buf[..read].to_vec()))
+    }
+
+    fn read_evdev(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut buf = [0u8; std::mem::size_of::<libc::input_event>()];
+        let Some(read) = self.read(&mut buf)? else {
+            return Ok(None);
+        };
+        if read < buf.len() {
+            return Ok(Some(Vec::new()));
+        }
+        // SAFETY: the buffer holds exactly one input_event
+        let event: libc::input_event = unsafe { std::ptr::read_unaligned(buf.as_ptr().cast()) };
+        // key presses only, not releases or autorepeat
+        if event.type_ != EV_KEY || event.value != 1 {
+            return Ok(Some(Vec::new()));
+        }
+        Ok(Some(evdev_key(event.code).into_iter().collect()))
+    }
+
+    fn read_hidraw(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut buf = [0u8; 64];
+        let Some(read) = self.read(&mut buf)? else {
+            return Ok(None);
+        };
+        if read < 8 {
+            return Ok(Some(Vec::new()));
+        }
+        // boot keyboard report: modifiers, reserved, then up to six keys held
+        let held: Vec<u8> = buf[2..8]
+            .iter()
+            .copied()
+            .filter(|&usage| usage != 0)
+            .collect();
+        let typed = held
+            .iter()
+            .filter(|usage| !self.pressed.contains(usage))
+            .filter_map(|&usage| hid_key(usage))
+            .collect();
+        self.pressed = held;
+        Ok(Some(typed))
+    }
+}
+
+fn configure_serial(file: &File, baud: u32) -> io::Result<()> {
+    let fd = file.as_raw_fd();
+    // SAFETY: isatty only inspects the open fd
+    if unsafe { libc::isatty(fd) } != 1 {
+        // a plain file or FIFO, nothing to set up
+        return Ok(());
+    }
+    let speed = match baud {
+        1200 => libc::B1200,
+        2400 => libc::B2400,
+        4800 => libc::B4800,
+        9600 => libc::B9600,
+        19200 => libc::B19200,
+        38400 => libc::B38400,
+        57600 => libc::B57600,
+        115200 => libc::B115200,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unsupported baud rate {}", baud),
+            ))
+        }
+    };
+    // SAFETY: termios is plain data filled in by tcgetattr before use
+    unsafe {
+        let mut termios: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(fd, &mut termios) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        libc::cfmakeraw(&mut termios);
+        libc::cfsetspeed(&mut termios, speed);
+        termios.c_cflag |= libc::CLOCAL | libc::CREAD;
+        termios.c_cc[libc::VMIN] = 1;
+        termios.c_cc[libc::VTIME] = 0;
+        if libc::tcsetattr(fd, libc::TCSANOW, &termios) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Linux key codes of the keys readers type
+fn evdev_key(code: u16) -> Option<u8> {
+    Some(match code {
+        2..=10 => b'1' + (code - 2) as u8,
+        11 => b'0',
+        28 | 96 => b'\n',
+        30 => b'A',
+        48 => b'B',
+        46 => b'C',
+        32 => b'D',
+        18 => b'E',
+        33 => b'F',
+        // keypad
+        79 => b'1',
+        80 => b'2',
+        81 => b'3',
+        75 => b'4',
+        76 => b'5',
+        77 => b'6',
+        71 => b'7',
+        72 => b'8',
+        73 => b'9',
+        82 => b'0',
+        _ => return None,
+    })
+}
+
+/// HID keyboard usage ids of the keys readers type
+fn hid_key(usage: u8) -> Option<u8> {
+    Some(match usage {
+        0x04..=0x09 => b'A' + (usage - 0x04),
+        0x1e..=0x26 => b'1' + (usage - 0x1e),
+        0x27 => b'0',
+        0x28 | 0x58 => b'\n',
+        0x59..=0x61 => b'1' + (usage - 0x59),
+        0x62 => b'0',
+        _ => return None,
+    })
+}
@@ -0,0 +1,8 @@
+//! Reads badges from a serial or USB RFID reader and posts them as scans,
+//! so scanning no longer depends on a focused browser window.
+
+pub mod client;
+
+pub mod frames;
+
+pub mod input;
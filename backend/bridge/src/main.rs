@@ -0,0 +1,86 @@
+use chrono::{Local, NaiveDateTime};
+use clap::Parser;
+use mvscan_bridge::{
+    client::Poster,
+    frames::{decode, Format},
+    input::{Input, Reader},
+};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+
+/// Read badges from an RFID reader and record them as scans at one location
+#[derive(Parser)]
+#[command(name = "mvscan-bridge", version)]
+struct Cli {
+    /// the reader, e.g. /dev/ttyUSB0, /dev/input/event3 or /dev/hidraw0
+    #[arg(long, env = "MVSCAN_DEVICE")]
+    device: PathBuf,
+    #[arg(long, value_enum, env = "MVSCAN_INPUT", default_value = "serial")]
+    input: Input,
+    #[arg(long, value_enum, env = "MVSCAN_FORMAT", default_value = "wedge")]
+    format: Format,
+    /// serial line speed
+    #[arg(long, env = "MVSCAN_BAUD", default_value_t = 9600)]
+    baud: u32,
+    /// base URL of the scanner API
+    #[arg(long, env = "MVSCAN_API", default_value = "http://localhost:8080/api")]
+    api: String,
+    /// location id every scan is recorded at
+    #[arg(long, env = "MVSCAN_LOCATION")]
+    location: i32,
+    /// name sent with each scan, used for debouncing; defaults to the device path
+    #[arg(long, env = "MVSCAN_SCANNER")]
+    scanner: Option<String>,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let cli = Cli::parse();
+    let scanner = cli
+        .scanner
+        .unwrap_or_else(|| cli.device.display().to_string());
+    let poster = Poster::new(&cli.api, cli.location, &scanner);
+    let mut reader = Reader::open(&cli.device, cli.input, cli.baud)?;
+    log::info!(
+        "reading {:?} badges from {} for location {}",
+        cli.format,
+        cli.device.display(),
+        cli.location
+    );
+    // posting, retries included, happens on its own thread so a slow or
+    // unreachable server never holds up reading the next badge
+    let (scans, queued) = mpsc::channel::<(String, NaiveDateTime)>();
+    let sender = thread::spawn(move || {
+        for (rfid, scanned_at) in queued {
+            match poster.post(&rfid, scanned_at) {
+                Ok(response) => log::info!("{}: {}", rfid, response["message"]),
+                Err(err) => log::error!("giving up on {}: {}", rfid, err),
+            }
+        }
+    });
+    let stopped = loop {
+        let frame = match reader.next_frame() {
+            Ok(Some(frame)) => frame,
+            Ok(None) => break format!("{} closed", cli.device.display()),
+            Err(err) => break format!("reading {}: {}", cli.device.display(), err),
+        };
+        // the badge was read when its frame completed, however long it waits to be sent
+        let scanned_at = Local::now().naive_local();
+        let rfid = match decode(cli.format, &frame) {
+            Ok(rfid) => rfid,
+            Err(err) => {
+                log::warn!("{}", err);
+                continue;
+            }
+        };
+        if scans.send((rfid, scanned_at)).is_err() {
+            break "posting thread stopped".to_string();
+        }
+    };
+    // send what was already read before exiting
+    drop(scans);
+    let _ = sender.join();
+    // exit non-zero so the service manager restarts us once the reader is back
+    Err(stopped.into())
+}
@@ -1,7 +1,20 @@
+pub mod alerts;
+pub mod appointments;
+pub mod idempotency_keys;
+pub mod location_corrections;
+pub mod location_level_caps;
+pub mod location_schedules;
 pub mod locations;
 pub mod prelude;
+pub mod resident_notes;
 pub mod residents;
+pub mod rules;
+pub mod sea_orm_active_enums;
+pub mod separations;
+pub mod shifts;
 pub mod timestamps;
+pub mod webhook_deliveries;
+pub mod webhooks;
 
 pub fn add(left: usize, right: usize) -> usize {
     left + right
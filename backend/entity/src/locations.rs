@@ -1,5 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.6
 use crate::prelude::OrmSerializable;
+use crate::sea_orm_active_enums::LocationKind;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -12,12 +13,40 @@ pub struct Model {
     pub id: i32,
     pub name: String,
     pub level: i32,
+    /// maximum residents whose `current_location` is this location
+    pub capacity: Option<i32>,
+    /// refuse arrivals over capacity instead of only warning
+    #[serde(default)]
+    pub refuse_over_capacity: bool,
+    /// enclosing location, e.g. the building a unit belongs to
+    pub parent_id: Option<i32>,
+    #[serde(default)]
+    pub kind: LocationKind,
+    /// refuse arrivals outside scheduled hours instead of only flagging them
+    #[serde(default)]
+    pub refuse_when_closed: bool,
+    /// repeat scans of a badge at the same scanner within this many seconds
+    /// are ignored, 0 turns debouncing off
+    #[serde(default)]
+    pub debounce_seconds: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::timestamps::Entity")]
     Timestamps,
+    #[sea_orm(has_many = "super::location_level_caps::Entity")]
+    LocationLevelCaps,
+    #[sea_orm(has_many = "super::location_schedules::Entity")]
+    LocationSchedules,
+    #[sea_orm(
+        belongs_to = "Entity",
+        from = "Column::ParentId",
+        to = "Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    SelfRef,
 }
 
 impl Related<super::timestamps::Entity> for Entity {
@@ -26,4 +55,16 @@ impl Related<super::timestamps::Entity> for Entity {
     }
 }
 
+impl Related<super::location_level_caps::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LocationLevelCaps.def()
+    }
+}
+
+impl Related<super::location_schedules::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LocationSchedules.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
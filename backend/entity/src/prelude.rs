@@ -1,11 +1,35 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.6
 
+pub use super::alerts::Entity as Alerts;
+pub use super::appointments::Entity as Appointments;
+pub use super::idempotency_keys::Entity as IdempotencyKeys;
+pub use super::location_corrections::Entity as LocationCorrections;
+pub use super::location_level_caps::Entity as LocationLevelCaps;
+pub use super::location_schedules::Entity as LocationSchedules;
 pub use super::locations::Entity as Locations;
+pub use super::resident_notes::Entity as ResidentNotes;
 pub use super::residents::Entity as Residents;
+pub use super::rules::Entity as Rules;
+pub use super::separations::Entity as Separations;
+pub use super::shifts::Entity as Shifts;
 pub use super::timestamps::Entity as Timestamps;
+pub use super::webhook_deliveries::Entity as WebhookDeliveries;
+pub use super::webhooks::Entity as Webhooks;
 
 pub trait OrmSerializable {}
+impl OrmSerializable for Alerts {}
+impl OrmSerializable for Appointments {}
+impl OrmSerializable for IdempotencyKeys {}
+impl OrmSerializable for LocationCorrections {}
+impl OrmSerializable for LocationLevelCaps {}
+impl OrmSerializable for LocationSchedules {}
 impl OrmSerializable for Locations {}
+impl OrmSerializable for ResidentNotes {}
 impl OrmSerializable for Residents {}
+impl OrmSerializable for Rules {}
+impl OrmSerializable for Separations {}
+impl OrmSerializable for Shifts {}
 impl OrmSerializable for Timestamps {}
+impl OrmSerializable for WebhookDeliveries {}
+impl OrmSerializable for Webhooks {}
 impl OrmSerializable for String {}
@@ -10,15 +10,20 @@ impl OrmSerializable for Model {}
 #[sea_orm(table_name = "residents")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = true)]
-    #[serde(skip)]
+    #[serde(default)]
     pub id: i32,
     pub rfid: String,
     pub name: String,
+    #[sea_orm(unique)]
     pub doc: String,
     pub room: String,
     pub unit: i32,
     pub current_location: i32,
     pub level: i32,
+    #[serde(default)]
+    pub photo_url: Option<String>,
+    #[serde(default)]
+    pub thumbnail_url: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -39,10 +44,18 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     Locations1,
+    #[sea_orm(has_many = "super::resident_notes::Entity")]
+    ResidentNotes,
     #[sea_orm(has_many = "super::timestamps::Entity")]
     Timestamps,
 }
 
+impl Related<super::resident_notes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ResidentNotes.def()
+    }
+}
+
 impl Related<super::timestamps::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Timestamps.def()
@@ -12,7 +12,14 @@ pub struct Model {
     pub id: i32,
     pub rfid: i32,
     pub location: i32,
+    /// when the badge was scanned, which may be earlier than it reached us
     pub ts: DateTime,
+    /// when the server recorded the scan
+    pub received_at: DateTime,
+    /// the reader that sent the scan, if it said
+    pub scanner: Option<String>,
+    /// the time the client sent, kept even when `ts` went by the server clock
+    pub scanned_at: Option<DateTime>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -6,6 +6,28 @@ mod m20231210_134639_timestamps;
 mod m20231210_150005_levels;
 mod m20231213_132412_seed_locations;
 mod m20231213_162420_seed_test_data;
+mod m20240108_101000_timestamps_ts_default;
+mod m20240108_101500_timestamps_history_index;
+mod m20240115_093000_separations;
+mod m20240115_094500_alerts;
+mod m20240122_140000_location_capacity;
+mod m20240129_110000_location_hierarchy;
+mod m20240205_083000_location_schedules;
+mod m20240212_091500_appointments;
+mod m20240219_070000_shifts;
+mod m20240226_100000_location_corrections;
+mod m20240304_090000_scan_debounce;
+mod m20240311_080000_timestamps_scan_time;
+mod m20240318_083000_canonical_badges;
+mod m20240325_090000_webhooks;
+mod m20240401_080000_rules;
+mod m20240404_080000_away_rules;
+mod m20240408_093000_resident_photos;
+mod m20240415_080000_resident_search;
+mod m20240422_090000_unique_resident_doc;
+mod m20240429_083000_resident_notes;
+mod m20240506_080000_timestamps_scanned_at;
+mod m20240513_080000_idempotency_request_hash;
 
 pub struct Migrator;
 
@@ -19,6 +41,28 @@ impl MigratorTrait for Migrator {
             Box::new(m20231210_150005_levels::Migration),
             Box::new(m20231213_132412_seed_locations::Migration),
             Box::new(m20231213_162420_seed_test_data::Migration),
+            Box::new(m20240108_101000_timestamps_ts_default::Migration),
+            Box::new(m20240108_101500_timestamps_history_index::Migration),
+            Box::new(m20240115_093000_separations::Migration),
+            Box::new(m20240115_094500_alerts::Migration),
+            Box::new(m20240122_140000_location_capacity::Migration),
+            Box::new(m20240129_110000_location_hierarchy::Migration),
+            Box::new(m20240205_083000_location_schedules::Migration),
+            Box::new(m20240212_091500_appointments::Migration),
+            Box::new(m20240219_070000_shifts::Migration),
+            Box::new(m20240226_100000_location_corrections::Migration),
+            Box::new(m20240304_090000_scan_debounce::Migration),
+            Box::new(m20240311_080000_timestamps_scan_time::Migration),
+            Box::new(m20240318_083000_canonical_badges::Migration),
+            Box::new(m20240325_090000_webhooks::Migration),
+            Box::new(m20240401_080000_rules::Migration),
+            Box::new(m20240404_080000_away_rules::Migration),
+            Box::new(m20240408_093000_resident_photos::Migration),
+            Box::new(m20240415_080000_resident_search::Migration),
+            Box::new(m20240422_090000_unique_resident_doc::Migration),
+            Box::new(m20240429_083000_resident_notes::Migration),
+            Box::new(m20240506_080000_timestamps_scanned_at::Migration),
+            Box::new(m20240513_080000_idempotency_request_hash::Migration),
         ]
     }
 }
@@ -1,32 +1,28 @@
-use entity::locations;
-use sea_orm::Set;
 use sea_orm_migration::prelude::*;
-use serde_json::from_str;
-
-use sea_orm::entity::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DbBackend, Statement};
 
 #[derive(DeriveMigrationName)]
 pub struct Migration;
 
+// The seed file is read as plain JSON in SQL, so later columns on locations
+// don't change what this inserts. Buildings are left to the hierarchy
+// migration, which added them to the file.
+const INSERT_SEEDED: &str = r#"
+INSERT INTO locations (id, name, level)
+SELECT id, name, 2 FROM json_to_recordset($1::json) AS seed(id int, name text, kind text)
+WHERE seed.kind IS DISTINCT FROM 'building'"#;
+
 #[async_trait::async_trait]
 impl MigrationTrait for Migration {
     async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
         let db = manager.get_connection();
         let file = std::fs::read_to_string("seed_data/locations.json").unwrap();
-        let locations = from_str::<Vec<locations::Model>>(&file).unwrap();
-
-        let active_locations: Vec<locations::ActiveModel> = locations
-            .iter()
-            .map(|loc| locations::ActiveModel {
-                id: Set(loc.id.to_owned()),
-                name: Set(loc.name.to_owned()),
-                level: Set(2),
-            })
-            .collect();
-
-        locations::Entity::insert_many(active_locations)
-            .exec(db)
-            .await?;
+        db.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            INSERT_SEEDED,
+            [file.into()],
+        ))
+        .await?;
 
         Ok(())
     }
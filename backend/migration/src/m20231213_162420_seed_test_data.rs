@@ -1,6 +1,5 @@
 use entity::residents;
 use entity::timestamps;
-use entity::timestamps::PostTimestamp;
 use entity::timestamps::SeedTimestamp;
 use sea_orm::Set;
 use sea_orm_migration::prelude::*;
@@ -0,0 +1,35 @@
+use entity::{prelude::Timestamps, timestamps};
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// The timestamps table was created with the time the migration ran as the
+// default for ts, so any row inserted without one was dated to that moment.
+// Default to the time of the insert instead.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Timestamps)
+                    .modify_column(
+                        ColumnDef::new(timestamps::Column::Ts)
+                            .timestamp()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // the old default was a fixed moment, not worth bringing back
+        manager
+            .get_connection()
+            .execute_unprepared("ALTER TABLE timestamps ALTER COLUMN ts DROP DEFAULT")
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use entity::{prelude::Timestamps, timestamps};
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Presence at time T is the newest timestamp per resident at or before T, so
+// (rfid, ts DESC) lets each resident's lookup be a single index probe.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_timestamps_rfid_ts")
+                    .table(Timestamps)
+                    .col(timestamps::Column::Rfid)
+                    .col((timestamps::Column::Ts, IndexOrder::Desc))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_timestamps_location_ts")
+                    .table(Timestamps)
+                    .col(timestamps::Column::Location)
+                    .col((timestamps::Column::Ts, IndexOrder::Desc))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_timestamps_location_ts")
+                    .table(Timestamps)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_timestamps_rfid_ts")
+                    .table(Timestamps)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,103 @@
+use entity::prelude::{LocationLevelCaps, Locations, Residents};
+use entity::{location_level_caps, locations, residents};
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Locations)
+                    .add_column(ColumnDef::new(locations::Column::Capacity).integer().null())
+                    .add_column(
+                        ColumnDef::new(locations::Column::RefuseOverCapacity)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(LocationLevelCaps)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(location_level_caps::Column::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(location_level_caps::Column::LocationId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(location_level_caps::Column::Level)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(location_level_caps::Column::Capacity)
+                            .integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_location_level_caps_locations")
+                            .from(LocationLevelCaps, location_level_caps::Column::LocationId)
+                            .to(Locations, locations::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .index(
+                        Index::create()
+                            .name("idx_location_level_caps_location_level")
+                            .col(location_level_caps::Column::LocationId)
+                            .col(location_level_caps::Column::Level)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        // occupancy is counted from residents.current_location on every scan
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_residents_current_location")
+                    .table(Residents)
+                    .col(residents::Column::CurrentLocation)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_residents_current_location")
+                    .table(Residents)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(LocationLevelCaps).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Locations)
+                    .drop_column(locations::Column::Capacity)
+                    .drop_column(locations::Column::RefuseOverCapacity)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,91 @@
+use entity::locations::{Column, Entity};
+use entity::sea_orm_active_enums::LocationKind;
+use sea_orm::ActiveEnum;
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// The seed file is read as plain JSON in SQL, so this keeps working whatever
+// columns later migrations add to locations.
+const INSERT_SEEDED: &str = r#"
+INSERT INTO locations (id, name, level)
+SELECT id, name, level FROM json_to_recordset($1::json) AS seed(id int, name text, level int)
+ON CONFLICT (id) DO NOTHING"#;
+
+const PLACE_SEEDED: &str = r#"
+UPDATE locations l
+SET kind = coalesce(seed.kind, 'program_area'), parent_id = seed.parent_id
+FROM json_to_recordset($1::json) AS seed(id int, kind text, parent_id int)
+WHERE l.id = seed.id"#;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Entity)
+                    .add_column(ColumnDef::new(Column::ParentId).integer().null())
+                    .add_column(
+                        ColumnDef::new(Column::Kind)
+                            .string_len(16)
+                            .not_null()
+                            .default(LocationKind::ProgramArea.to_value()),
+                    )
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_locations_parent")
+                            .from_tbl(Entity)
+                            .from_col(Column::ParentId)
+                            .to_tbl(Entity)
+                            .to_col(Column::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_locations_parent_id")
+                    .table(Entity)
+                    .col(Column::ParentId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // place the seeded locations into buildings: add any new seed rows first
+        // so every parent exists, then set each location's kind and parent
+        let db = manager.get_connection();
+        let seeded = std::fs::read_to_string("seed_data/locations.json").map_err(|err| {
+            DbErr::Custom(format!(
+                "reading seed_data/locations.json (run from the backend directory): {}",
+                err
+            ))
+        })?;
+        for sql in [INSERT_SEEDED, PLACE_SEEDED] {
+            db.execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                sql,
+                [seeded.clone().into()],
+            ))
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Entity)
+                    .drop_foreign_key(Alias::new("fk_locations_parent"))
+                    .drop_column(Column::ParentId)
+                    .drop_column(Column::Kind)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,86 @@
+use entity::location_schedules::*;
+use entity::locations;
+use entity::prelude::Locations;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Locations)
+                    .add_column(
+                        ColumnDef::new(locations::Column::RefuseWhenClosed)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Column::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Column::LocationId).integer().not_null())
+                    .col(ColumnDef::new(Column::Weekday).integer().not_null())
+                    .col(ColumnDef::new(Column::OpensAt).time().not_null())
+                    .col(ColumnDef::new(Column::ClosesAt).time().not_null())
+                    .col(
+                        ColumnDef::new(Column::AllowedLevels)
+                            .array(ColumnType::Integer)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Column::AllowedUnits)
+                            .array(ColumnType::Integer)
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_location_schedules_locations")
+                            .from(Entity, Column::LocationId)
+                            .to(Locations, locations::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_location_schedules_location_weekday")
+                    .table(Entity)
+                    .col(Column::LocationId)
+                    .col(Column::Weekday)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Entity).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Locations)
+                    .drop_column(locations::Column::RefuseWhenClosed)
+                    .to_owned(),
+            )
+            .await
+    }
+}
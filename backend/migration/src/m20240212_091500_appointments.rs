@@ -0,0 +1,94 @@
+use entity::appointments::*;
+use entity::prelude::{Locations, Residents};
+use entity::sea_orm_active_enums::{AppointmentKind, AppointmentStatus};
+use entity::{locations, residents};
+use sea_orm::ActiveEnum;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Column::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Column::ResidentId).integer().not_null())
+                    .col(ColumnDef::new(Column::LocationId).integer().not_null())
+                    .col(
+                        ColumnDef::new(Column::Kind)
+                            .string_len(16)
+                            .not_null()
+                            .default(AppointmentKind::Other.to_value()),
+                    )
+                    .col(ColumnDef::new(Column::StartsAt).timestamp().not_null())
+                    .col(ColumnDef::new(Column::EndsAt).timestamp().not_null())
+                    .col(
+                        ColumnDef::new(Column::Status)
+                            .string_len(16)
+                            .not_null()
+                            .default(AppointmentStatus::Scheduled.to_value()),
+                    )
+                    .col(ColumnDef::new(Column::ArrivedAt).timestamp().null())
+                    .col(ColumnDef::new(Column::Note).string().null())
+                    .col(
+                        ColumnDef::new(Column::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_appointments_resident")
+                            .from(Entity, Column::ResidentId)
+                            .to(Residents, residents::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_appointments_location")
+                            .from(Entity, Column::LocationId)
+                            .to(Locations, locations::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_appointments_resident_starts_at")
+                    .table(Entity)
+                    .col(Column::ResidentId)
+                    .col(Column::StartsAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_appointments_location_starts_at")
+                    .table(Entity)
+                    .col(Column::LocationId)
+                    .col(Column::StartsAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Entity).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,64 @@
+use entity::location_corrections::*;
+use entity::prelude::Residents;
+use entity::residents;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Column::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Column::ResidentId).integer().not_null())
+                    .col(ColumnDef::new(Column::StoredLocation).integer().not_null())
+                    .col(
+                        ColumnDef::new(Column::ReplayedLocation)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Column::Source).string_len(16).not_null())
+                    .col(
+                        ColumnDef::new(Column::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_location_corrections_resident")
+                            .from(Entity, Column::ResidentId)
+                            .to(Residents, residents::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_location_corrections_resident")
+                    .table(Entity)
+                    .col(Column::ResidentId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Entity).to_owned())
+            .await
+    }
+}
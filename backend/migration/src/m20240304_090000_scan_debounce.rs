@@ -0,0 +1,68 @@
+use entity::{idempotency_keys, locations};
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(locations::Entity)
+                    .add_column(
+                        ColumnDef::new(locations::Column::DebounceSeconds)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(idempotency_keys::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(idempotency_keys::Column::Key)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(idempotency_keys::Column::Status)
+                            .integer()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(idempotency_keys::Column::Response)
+                            .json()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(idempotency_keys::Column::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(idempotency_keys::Entity).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(locations::Entity)
+                    .drop_column(locations::Column::DebounceSeconds)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,49 @@
+use entity::timestamps;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(timestamps::Entity)
+                    .add_column(
+                        ColumnDef::new(timestamps::Column::ReceivedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .add_column(ColumnDef::new(timestamps::Column::Scanner).string().null())
+                    .to_owned(),
+            )
+            .await?;
+        // until now every scan was stamped when it arrived
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(timestamps::Entity)
+                    .value(
+                        timestamps::Column::ReceivedAt,
+                        Expr::col(timestamps::Column::Ts),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(timestamps::Entity)
+                    .drop_column(timestamps::Column::ReceivedAt)
+                    .drop_column(timestamps::Column::Scanner)
+                    .to_owned(),
+            )
+            .await
+    }
+}
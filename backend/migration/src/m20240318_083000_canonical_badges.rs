@@ -0,0 +1,92 @@
+use mvscan_badges::badges::canonical;
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DbBackend, Statement};
+use std::collections::HashMap;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const RESIDENT_BADGES: &str = "SELECT id, rfid, name, doc FROM residents ORDER BY id";
+
+const SET_BADGE: &str = "UPDATE residents SET rfid = $1 WHERE id = $2";
+
+// Badge ids are now stored the way scans are looked up: hex, Wiegand, EM4100
+// and facility/card ids as one decimal number, without leading zeros.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let rows = db
+            .query_all(Statement::from_string(DbBackend::Postgres, RESIDENT_BADGES))
+            .await?;
+        let mut residents = Vec::with_capacity(rows.len());
+        for row in rows {
+            residents.push((
+                row.try_get::<i32>("", "id")?,
+                row.try_get::<String>("", "rfid")?,
+                row.try_get::<String>("", "name")?,
+                row.try_get::<String>("", "doc")?,
+            ));
+        }
+        let mut owners: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut problems = Vec::new();
+        let mut converted = Vec::with_capacity(residents.len());
+        for (index, (id, rfid, name, doc)) in residents.iter().enumerate() {
+            match canonical(rfid) {
+                Ok(badge) => {
+                    owners.entry(badge.clone()).or_default().push(index);
+                    converted.push(Some(badge));
+                }
+                Err(err) => {
+                    problems.push(format!(
+                        "{} (id {}, DOC {}, stored as {}): {}",
+                        name, id, doc, rfid, err
+                    ));
+                    converted.push(None);
+                }
+            }
+        }
+        let mut shared: Vec<_> = owners.iter().filter(|(_, held)| held.len() > 1).collect();
+        shared.sort();
+        for (badge, held) in shared {
+            let holders: Vec<String> = held
+                .iter()
+                .map(|&index| {
+                    let (id, rfid, name, _) = &residents[index];
+                    format!("{} (id {}, stored as {})", name, id, rfid)
+                })
+                .collect();
+            problems.push(format!(
+                "badge {} is held by {}",
+                badge,
+                holders.join(" and ")
+            ));
+        }
+        // which resident a badge really belongs to is for an admin to say, so
+        // refuse to go on rather than leave anyone unreachable by scans
+        if !problems.is_empty() {
+            return Err(DbErr::Migration(format!(
+                "Badge ids must decode to one resident each, give these residents a badge of \
+                 their own (mvscan-admin badges reassign <stored as> <badge>) or release them \
+                 and run the migration again: {}",
+                problems.join("; ")
+            )));
+        }
+        for ((id, rfid, _, _), badge) in residents.iter().zip(converted.into_iter().flatten()) {
+            if *rfid != badge {
+                db.execute(Statement::from_sql_and_values(
+                    DbBackend::Postgres,
+                    SET_BADGE,
+                    [badge.into(), (*id).into()],
+                ))
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // the ids as first stored aren't recorded anywhere
+        Ok(())
+    }
+}
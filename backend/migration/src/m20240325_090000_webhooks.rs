@@ -0,0 +1,146 @@
+use entity::prelude::Webhooks;
+use entity::sea_orm_active_enums::DeliveryStatus;
+use entity::{webhook_deliveries, webhooks};
+use sea_orm::ActiveEnum;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(webhooks::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(webhooks::Column::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(webhooks::Column::Url).string().not_null())
+                    .col(
+                        ColumnDef::new(webhooks::Column::Events)
+                            .array(ColumnType::String(None))
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(webhooks::Column::Secret).string().not_null())
+                    .col(
+                        ColumnDef::new(webhooks::Column::Active)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(webhooks::Column::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(webhook_deliveries::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(webhook_deliveries::Column::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_deliveries::Column::WebhookId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_deliveries::Column::Event)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_deliveries::Column::Payload)
+                            .json()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_deliveries::Column::Status)
+                            .string_len(16)
+                            .not_null()
+                            .default(DeliveryStatus::Pending.to_value()),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_deliveries::Column::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_deliveries::Column::NextAttemptAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_deliveries::Column::ResponseStatus)
+                            .integer()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_deliveries::Column::Error)
+                            .string()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_deliveries::Column::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_deliveries::Column::DeliveredAt)
+                            .timestamp()
+                            .null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_webhook_deliveries_webhook")
+                            .from(
+                                webhook_deliveries::Entity,
+                                webhook_deliveries::Column::WebhookId,
+                            )
+                            .to(Webhooks, webhooks::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_deliveries_status_next_attempt")
+                    .table(webhook_deliveries::Entity)
+                    .col(webhook_deliveries::Column::Status)
+                    .col(webhook_deliveries::Column::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(webhook_deliveries::Entity).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(webhooks::Entity).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,129 @@
+use entity::prelude::{Locations, Rules};
+use entity::{alerts, locations, rules};
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(rules::Entity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(rules::Column::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(rules::Column::Name).string().not_null())
+                    .col(
+                        ColumnDef::new(rules::Column::Kind)
+                            .string_len(16)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(rules::Column::Minutes).integer().null())
+                    .col(ColumnDef::new(rules::Column::Location).integer().null())
+                    .col(ColumnDef::new(rules::Column::Unit).integer().null())
+                    .col(ColumnDef::new(rules::Column::MaxCount).integer().null())
+                    .col(
+                        ColumnDef::new(rules::Column::ResidentLevel)
+                            .integer()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(rules::Column::LocationLevel)
+                            .integer()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(rules::Column::Active)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(rules::Column::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_rules_location")
+                            .from(rules::Entity, rules::Column::Location)
+                            .to(Locations, locations::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(alerts::Entity)
+                    .add_column(ColumnDef::new(alerts::Column::RuleId).integer().null())
+                    .add_column(
+                        ColumnDef::new(alerts::Column::AcknowledgedAt)
+                            .timestamp()
+                            .null(),
+                    )
+                    .add_column(
+                        ColumnDef::new(alerts::Column::AcknowledgedBy)
+                            .string()
+                            .null(),
+                    )
+                    .add_column(
+                        ColumnDef::new(alerts::Column::ResolvedAt)
+                            .timestamp()
+                            .null(),
+                    )
+                    .add_column(ColumnDef::new(alerts::Column::ResolvedBy).string().null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_alerts_rule")
+                            .from_tbl(alerts::Entity)
+                            .from_col(alerts::Column::RuleId)
+                            .to_tbl(Rules)
+                            .to_col(rules::Column::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        // rule evaluation looks up a rule's open alerts on every pass
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_alerts_rule_resolved_at")
+                    .table(alerts::Entity)
+                    .col(alerts::Column::RuleId)
+                    .col(alerts::Column::ResolvedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(alerts::Entity)
+                    .drop_foreign_key(Alias::new("fk_alerts_rule"))
+                    .drop_column(alerts::Column::RuleId)
+                    .drop_column(alerts::Column::AcknowledgedAt)
+                    .drop_column(alerts::Column::AcknowledgedBy)
+                    .drop_column(alerts::Column::ResolvedAt)
+                    .drop_column(alerts::Column::ResolvedBy)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(rules::Entity).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Residents left in AWAY are flagged by away_too_long rules, start out with
+// alerts at 30 and 60 minutes. Supervisors can change or pause them like any
+// other rule.
+const SEED_AWAY_RULES: &str = r#"
+INSERT INTO rules (name, kind, minutes)
+VALUES ('AWAY over 30 minutes', 'away_too_long', 30),
+       ('AWAY over 60 minutes', 'away_too_long', 60)"#;
+
+const REMOVE_AWAY_RULES: &str = r#"
+DELETE FROM rules
+WHERE kind = 'away_too_long'
+  AND name IN ('AWAY over 30 minutes', 'AWAY over 60 minutes')"#;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(SEED_AWAY_RULES)
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(REMOVE_AWAY_RULES)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use entity::prelude::Residents;
+use entity::residents;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Residents)
+                    .add_column(ColumnDef::new(residents::Column::PhotoUrl).string().null())
+                    .add_column(
+                        ColumnDef::new(residents::Column::ThumbnailUrl)
+                            .string()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Residents)
+                    .drop_column(residents::Column::PhotoUrl)
+                    .drop_column(residents::Column::ThumbnailUrl)
+                    .to_owned(),
+            )
+            .await
+    }
+}
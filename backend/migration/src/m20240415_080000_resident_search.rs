@@ -0,0 +1,35 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// GET /api/residents/search matches names by trigram similarity and DOC numbers
+// and rooms by (case-insensitive) prefix, which trigram indexes answer too.
+// Badge prefixes need an index of their own: the unique one on rfid follows
+// the database collation, which LIKE 'x%' can't use.
+const UP: &str = r#"
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+CREATE INDEX IF NOT EXISTS idx_residents_name_trgm ON residents USING gin (name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_residents_doc_trgm ON residents USING gin (doc gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_residents_room_trgm ON residents USING gin (room gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_residents_rfid_prefix ON residents (rfid text_pattern_ops);"#;
+
+// the extension stays, dropping it would take anything else built on it too
+const DOWN: &str = r#"
+DROP INDEX IF EXISTS idx_residents_name_trgm;
+DROP INDEX IF EXISTS idx_residents_doc_trgm;
+DROP INDEX IF EXISTS idx_residents_room_trgm;
+DROP INDEX IF EXISTS idx_residents_rfid_prefix;"#;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.get_connection().execute_unprepared(UP).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.get_connection().execute_unprepared(DOWN).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use entity::prelude::Residents;
+use entity::residents;
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const TRIM_DOCS: &str = "UPDATE residents SET doc = btrim(doc) WHERE doc <> btrim(doc)";
+
+const SHARED_DOCS: &str = r#"
+SELECT doc || ': ' || string_agg(name || ' (' || rfid || ')', ', ' ORDER BY id) AS shared
+FROM residents
+GROUP BY doc
+HAVING count(*) > 1
+ORDER BY doc"#;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(TRIM_DOCS).await?;
+        // which of two residents really has a DOC number is for an admin to say,
+        // so refuse to go on rather than guess
+        let shared = db
+            .query_all(Statement::from_string(DbBackend::Postgres, SHARED_DOCS))
+            .await?
+            .iter()
+            .map(|row| row.try_get::<String>("", "shared"))
+            .collect::<Result<Vec<_>, _>>()?;
+        if !shared.is_empty() {
+            return Err(DbErr::Migration(format!(
+                "DOC numbers must be unique, give each of these residents their own \
+                 (mvscan-admin residents update <rfid> --doc <doc>) and run the migration again: {}",
+                shared.join("; ")
+            )));
+        }
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_residents_doc")
+                    .table(Residents)
+                    .col(residents::Column::Doc)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_residents_doc")
+                    .table(Residents)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,88 @@
+use entity::prelude::{ResidentNotes, Residents};
+use entity::{resident_notes, residents};
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ResidentNotes)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(resident_notes::Column::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(resident_notes::Column::ResidentId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(resident_notes::Column::Author)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(resident_notes::Column::Category)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(resident_notes::Column::Note)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(resident_notes::Column::Severity)
+                            .string_len(16)
+                            .not_null()
+                            .default("medium"),
+                    )
+                    .col(
+                        ColumnDef::new(resident_notes::Column::ExpiresAt)
+                            .timestamp()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(resident_notes::Column::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_resident_notes_resident")
+                            .from(ResidentNotes, resident_notes::Column::ResidentId)
+                            .to(Residents, residents::Column::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        // every scan looks up the resident's high severity notes
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_resident_notes_resident_severity")
+                    .table(ResidentNotes)
+                    .col(resident_notes::Column::ResidentId)
+                    .col(resident_notes::Column::Severity)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ResidentNotes).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,57 @@
+use entity::{prelude::Timestamps, timestamps};
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// A flagged scan is recorded at the server's time, so `ts` no longer says what
+// the client sent. Keeping its `scanned_at` lets a resent scan be recognised.
+// Earlier rows don't record whether the client sent a time, so theirs stays
+// NULL rather than being guessed from `ts` and `received_at`.
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Timestamps)
+                    .add_column(
+                        ColumnDef::new(timestamps::Column::ScannedAt)
+                            .timestamp()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_timestamps_rfid_scanned_at")
+                    .table(Timestamps)
+                    .col(timestamps::Column::Rfid)
+                    .col(timestamps::Column::ScannedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_timestamps_rfid_scanned_at")
+                    .table(Timestamps)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Timestamps)
+                    .drop_column(timestamps::Column::ScannedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
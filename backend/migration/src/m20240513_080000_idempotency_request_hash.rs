@@ -0,0 +1,53 @@
+use entity::idempotency_keys;
+use entity::prelude::IdempotencyKeys;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // keys stored before this have no hash and replay for any request
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(IdempotencyKeys)
+                    .add_column(
+                        ColumnDef::new(idempotency_keys::Column::RequestHash)
+                            .string()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_idempotency_keys_created_at")
+                    .table(IdempotencyKeys)
+                    .col(idempotency_keys::Column::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_idempotency_keys_created_at")
+                    .table(IdempotencyKeys)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(IdempotencyKeys)
+                    .drop_column(idempotency_keys::Column::RequestHash)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -1,7 +1,8 @@
 use std::env::var;
+use std::time::Duration;
 
 use migration::{Migrator, MigratorTrait};
-use sea_orm::{ConnectOptions, Database, DatabaseConnection};
+use sea_orm::{ConnectOptions, Database, DatabaseConnection, DbErr};
 
 #[derive(Clone)]
 pub struct DB(pub DatabaseConnection);
@@ -10,10 +11,36 @@ impl DB {
     pub async fn get() -> Result<Self, Box<dyn std::error::Error>> {
         dotenvy::dotenv().expect("failed to read .env file");
         let db_path = var("DATABASE_URL").unwrap();
+        Self::connect(&db_path).await
+    }
+
+    /// Connect to `db_path` and bring the schema up to date
+    pub async fn connect(db_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let db = Self::open(db_path).await?;
+        db.migrate().await?;
+        Ok(db)
+    }
+
+    /// Connect to `db_path`, leaving the schema as it is
+    pub async fn open(db_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
         log::info!("Connecting to database: {}", db_path);
-        let options = ConnectOptions::new(&db_path);
-        let conn: DatabaseConnection = Database::connect(options).await.unwrap();
-        Migrator::up(&conn, None).await?;
+        let options = ConnectOptions::new(db_path);
+        let conn: DatabaseConnection = Database::connect(options).await?;
         Ok(DB(conn))
     }
+
+    /// Apply the migrations that haven't run yet, stopping at the first that fails
+    pub async fn migrate(&self) -> Result<(), DbErr> {
+        Migrator::up(&self.0, None).await
+    }
+}
+
+/// `name` as a whole number of seconds, at least one, `default` when unset or unreadable
+pub fn env_seconds(name: &str, default: u64) -> Duration {
+    let seconds = var(name)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(default)
+        .max(1);
+    Duration::from_secs(seconds)
 }
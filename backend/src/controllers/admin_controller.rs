@@ -0,0 +1,29 @@
+use crate::app_config::DB;
+use crate::models::{
+    consistency::{find_discrepancies, repair, Discrepancy, RepairParams},
+    response::Response,
+};
+use actix_web::{get, http::header::ContentType, post, web, HttpResponse};
+
+// residents whose current_location disagrees with their timestamps
+#[rustfmt::skip]
+#[get("/api/admin/consistency")]
+pub async fn consistency(db: web::Data<DB>, params: web::Query<RepairParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let found = find_discrepancies(db, params.rfid.as_deref()).await?;
+    let response: Response<Discrepancy> = Response::from(found);
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+}
+
+// ?trust=history (default) moves residents to their replayed location,
+// ?trust=resident records a timestamp for their stored one
+#[rustfmt::skip]
+#[post("/api/admin/consistency/repair")]
+pub async fn repair_consistency(db: web::Data<DB>, params: web::Query<RepairParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let params = params.into_inner();
+    let repaired = repair(db, params.trust.unwrap_or_default(), params.rfid.as_deref()).await?;
+    let mut response: Response<Discrepancy> = Response::from(repaired);
+    response.message = format!("Repaired {} residents", response.data.as_ref().map_or(0, Vec::len));
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+}
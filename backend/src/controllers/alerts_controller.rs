@@ -0,0 +1,68 @@
+use crate::app_config::DB;
+use crate::models::{
+    alerts::{self as alert_model, AlertAction, AlertParams, AlertStatus},
+    response::Response,
+};
+use actix_web::{get, http::header::ContentType, post, web, HttpResponse};
+use chrono::Local;
+use entity::alerts::{self, Entity as Alerts};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, PaginatorTrait, QueryFilter,
+    QueryOrder, Set,
+};
+
+/// GET: /api/alerts?kind=separation&status=open&rule=1&page=0  Newest first
+#[rustfmt::skip]
+#[get("/api/alerts")]
+pub async fn index(db: web::Data<DB>, params: web::Query<AlertParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let params = params.into_inner();
+    let mut query = Alerts::find().order_by_desc(alerts::Column::CreatedAt).order_by_desc(alerts::Column::Id);
+    if let Some(kind) = params.kind {
+        query = query.filter(alerts::Column::Kind.eq(kind));
+    }
+    if let Some(rule) = params.rule {
+        query = query.filter(alerts::Column::RuleId.eq(rule));
+    }
+    query = match params.status {
+        Some(AlertStatus::Open) => query.filter(alerts::Column::ResolvedAt.is_null()),
+        Some(AlertStatus::Acknowledged) => query.filter(alerts::Column::ResolvedAt.is_null()).filter(alerts::Column::AcknowledgedAt.is_not_null()),
+        Some(AlertStatus::Resolved) => query.filter(alerts::Column::ResolvedAt.is_not_null()),
+        None => query,
+    };
+    let alerts = query.paginate(db, 50).fetch_page(params.page.unwrap_or(0)).await?;
+    let response: Response<alerts::Model> = Response::from(alerts);
+    Ok(HttpResponse::Ok().content_type(ContentType::json()).json(response))
+}
+
+// staff have seen the alert and are dealing with it
+#[rustfmt::skip]
+#[post("/api/alerts/{id}/acknowledge")]
+pub async fn acknowledge(db: web::Data<DB>, id: web::Path<i32>, action: web::Json<AlertAction>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let Some(alert) = Alerts::find_by_id(id.into_inner()).one(db).await? else {
+        return Ok(HttpResponse::Ok().content_type(ContentType::json()).json(Response::<String>::alert_not_found()));
+    };
+    if alert.acknowledged_at.is_some() {
+        return Ok(HttpResponse::Ok().content_type(ContentType::json()).json(Response::<String>::from_error("Alert is already acknowledged")));
+    }
+    let mut alert = alert.into_active_model();
+    alert.acknowledged_at = Set(Some(Local::now().naive_local()));
+    alert.acknowledged_by = Set(Some(action.into_inner().by));
+    let response: Response<alerts::Model> = Response::from(alert.update(db).await?);
+    Ok(HttpResponse::Ok().content_type(ContentType::json()).json(response))
+}
+
+#[rustfmt::skip]
+#[post("/api/alerts/{id}/resolve")]
+pub async fn resolve(db: web::Data<DB>, id: web::Path<i32>, action: web::Json<AlertAction>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let Some(alert) = Alerts::find_by_id(id.into_inner()).one(db).await? else {
+        return Ok(HttpResponse::Ok().content_type(ContentType::json()).json(Response::<String>::alert_not_found()));
+    };
+    if alert.resolved_at.is_some() {
+        return Ok(HttpResponse::Ok().content_type(ContentType::json()).json(Response::<String>::from_error("Alert is already resolved")));
+    }
+    let response: Response<alerts::Model> = Response::from(alert_model::resolve(db, alert, &action.into_inner().by).await?);
+    Ok(HttpResponse::Ok().content_type(ContentType::json()).json(response))
+}
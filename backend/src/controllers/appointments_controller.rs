@@ -0,0 +1,149 @@
+use crate::app_config::DB;
+use crate::models::{
+    appointments::{
+        appointment_views, AppointmentParams, AppointmentView, NewAppointment, UpdateAppointment,
+    },
+    badges,
+    locations::location_and_descendants,
+    response::Response,
+};
+use actix_web::{delete, get, http::header::ContentType, patch, post, web, HttpResponse};
+use chrono::{Duration, Local};
+use entity::{
+    appointments::{self, Entity as Appointments},
+    locations::Entity as Locations,
+    residents::Entity as Residents,
+    sea_orm_active_enums::AppointmentStatus,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, ModelTrait, QueryFilter,
+    QueryOrder, Set,
+};
+
+// index appointments by ?date=, ?location=, ?rfid= and ?status=, earliest first
+#[rustfmt::skip]
+#[get("/api/appointments")]
+pub async fn index(db: web::Data<DB>, params: web::Query<AppointmentParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let params = params.into_inner();
+    let mut query = Appointments::find().order_by_asc(appointments::Column::StartsAt);
+    if let Some(date) = params.date {
+        let start = date.and_hms_opt(0, 0, 0).unwrap();
+        query = query
+            .filter(appointments::Column::StartsAt.gte(start))
+            .filter(appointments::Column::StartsAt.lt(start + Duration::days(1)));
+    }
+    if let Some(location) = params.location {
+        query = query.filter(appointments::Column::LocationId.is_in(location_and_descendants(db, location).await?));
+    }
+    if let Some(rfid) = params.rfid {
+        let Some(resident) = Residents::find().filter(badges::matches(&rfid)).one(db).await? else {
+            return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::resident_not_found()));
+        };
+        query = query.filter(appointments::Column::ResidentId.eq(resident.id));
+    }
+    if let Some(status) = params.status {
+        query = query.filter(appointments::Column::Status.eq(status));
+    }
+    let appts = appointment_views(db, query.all(db).await?).await?;
+    let response: Response<AppointmentView> = Response::from(appts);
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+}
+
+#[rustfmt::skip]
+#[get("/api/appointments/{id}")]
+pub async fn show(db: web::Data<DB>, id: web::Path<i32>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    match Appointments::find_by_id(id.into_inner()).one(db).await? {
+        Some(appt) => {
+            let view = appointment_views(db, vec![appt]).await?.remove(0);
+            let response: Response<AppointmentView> = Response::from(view);
+            Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+        }
+        None => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Appointment not found"))),
+    }
+}
+
+#[rustfmt::skip]
+#[post("/api/appointments")]
+pub async fn store(db: web::Data<DB>, appt: web::Json<NewAppointment>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let appt = appt.into_inner();
+    let Some(resident) = Residents::find().filter(badges::matches(&appt.rfid)).one(db).await? else {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::resident_not_found()));
+    };
+    if Locations::find_by_id(appt.location).one(db).await?.is_none() {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Error retrieving location")));
+    }
+    if appt.ends_at <= appt.starts_at {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Appointment must end after it starts")));
+    }
+    let new_appt = appointments::ActiveModel {
+        resident_id: Set(resident.id),
+        location_id: Set(appt.location),
+        kind: Set(appt.kind),
+        starts_at: Set(appt.starts_at),
+        ends_at: Set(appt.ends_at),
+        status: Set(AppointmentStatus::Scheduled),
+        note: Set(appt.note),
+        created_at: Set(Local::now().naive_local()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    let view = appointment_views(db, vec![new_appt]).await?.remove(0);
+    let response: Response<AppointmentView> = Response::from(view);
+    Ok(HttpResponse::Created().insert_header(ContentType::json()).json(response))
+}
+
+// moving an appointment in time or place puts it back to scheduled
+#[rustfmt::skip]
+#[patch("/api/appointments/{id}")]
+pub async fn update(db: web::Data<DB>, id: web::Path<i32>, appt: web::Json<UpdateAppointment>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let appt = appt.into_inner();
+    let Some(existing) = Appointments::find_by_id(id.into_inner()).one(db).await? else {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Appointment not found")));
+    };
+    let starts_at = appt.starts_at.unwrap_or(existing.starts_at);
+    let ends_at = appt.ends_at.unwrap_or(existing.ends_at);
+    if ends_at <= starts_at {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Appointment must end after it starts")));
+    }
+    let mut to_update = existing.clone().into_active_model();
+    if let Some(location) = appt.location {
+        if Locations::find_by_id(location).one(db).await?.is_none() {
+            return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Error retrieving location")));
+        }
+        to_update.location_id = Set(location);
+    }
+    if let Some(kind) = appt.kind {
+        to_update.kind = Set(kind);
+    }
+    if let Some(note) = appt.note {
+        to_update.note = Set(note);
+    }
+    to_update.starts_at = Set(starts_at);
+    to_update.ends_at = Set(ends_at);
+    if starts_at != existing.starts_at || ends_at != existing.ends_at || appt.location.is_some_and(|loc| loc != existing.location_id) {
+        to_update.status = Set(AppointmentStatus::Scheduled);
+        to_update.arrived_at = Set(None);
+    }
+    let updated = to_update.update(db).await?;
+    let view = appointment_views(db, vec![updated]).await?.remove(0);
+    let response: Response<AppointmentView> = Response::from(view);
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+}
+
+#[rustfmt::skip]
+#[delete("/api/appointments/{id}")]
+pub async fn destroy(db: web::Data<DB>, id: web::Path<i32>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    match Appointments::find_by_id(id.into_inner()).one(db).await? {
+        Some(appt) => {
+            appt.delete(db).await?;
+            Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_success("Appointment successfully removed")))
+        }
+        None => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Appointment not found"))),
+    }
+}
@@ -1,17 +1,23 @@
 use super::timestamps_controller::FilterOpts;
 use crate::app_config::DB;
+use crate::models::locations::{
+    descendants_of, location_and_descendants, location_views, LocationView, UpdateLocation,
+};
+use crate::models::occupancy::{occupants_at, AtParams, Occupant};
 use crate::models::response::Response;
+use crate::models::shifts::{resolve_shift, shift_window, ShiftParams};
+use crate::models::timestamps::{timestamps_between, timestamps_within};
 use actix_web::http::header::ContentType;
-use actix_web::{get, post, web, HttpResponse, Responder, ResponseError};
-use chrono::{Days, NaiveDate};
+use actix_web::{get, patch, post, web, HttpResponse, Responder, ResponseError};
+use chrono::{Local, NaiveDate};
 use entity::{
+    location_level_caps::{self, Entity as LocationLevelCaps},
     locations::{self, Entity as Locations},
     residents::Entity as Residents,
-    timestamps::Entity as Timestamps,
 };
 use entity::{residents, timestamps};
 use sea_orm::entity::prelude::*;
-use sea_orm::{QueryOrder, Set};
+use sea_orm::{IntoActiveModel, QueryOrder, Set, TransactionTrait};
 use serde::{Deserialize, Deserializer};
 use std::fmt::{Display, Formatter};
 
@@ -26,6 +32,8 @@ pub struct LocationRange {
 #[derive(Debug, Deserialize)]
 pub struct Params {
     pub current: Option<bool>,
+    /// include sub-locations, defaults to false
+    pub rollup: Option<bool>,
 }
 
 // Deserialize date strings into NaiveDate
@@ -41,6 +49,19 @@ pub struct Id {
     pub location_id: usize,
 }
 
+// the location alone, or everything beneath it too when the caller asked for ?rollup=true
+async fn scope(
+    db: &DatabaseConnection,
+    id: usize,
+    rollup: Option<bool>,
+) -> Result<Vec<i32>, DbErr> {
+    if rollup == Some(true) {
+        location_and_descendants(db, id as i32).await
+    } else {
+        Ok(vec![id as i32])
+    }
+}
+
 impl Display for Id {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "Location Id: {}", self.location_id)
@@ -57,14 +78,14 @@ impl std::fmt::Display for LocationsError {
     }
 }
 
-// index all locations
+// index all locations with their current occupancy
 #[rustfmt::skip]
 #[get("/api/locations")]
 pub async fn index(db: web::Data<DB>) -> impl Responder {
     let db = &db.0;
     log::info!("GET: locations controller");
-    if let Ok(location) = Locations::find().all(db).await {
-    let response: Response<locations::Model> = Response::from(location);
+    if let Ok(views) = location_views(db, None).await {
+    let response: Response<LocationView> = Response::from(views);
     HttpResponse::Ok().insert_header(ContentType::json()).json(response)
     } else {
         let response = Response::<String>::from_error("Error retrieving locations");
@@ -79,10 +100,21 @@ pub async fn store(db: web::Data<DB>, loc: web::Json<locations::Model>) -> impl
     let db = &db.0;
     log::info!("POST: locations controller");
     let loc = loc.into_inner();
+    // the same checks an update of these settings gets
+    let settings = UpdateLocation { capacity: Some(loc.capacity), ..Default::default() };
+    if let Err(message) = settings.validate() {
+        return HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error(&message));
+    }
     let location = locations::ActiveModel {
         id: Set(loc.id),
         name: Set(loc.name),
-        ..Default::default()
+        level: Set(loc.level),
+        capacity: Set(loc.capacity),
+        refuse_over_capacity: Set(loc.refuse_over_capacity),
+        refuse_when_closed: Set(loc.refuse_when_closed),
+        debounce_seconds: Set(loc.debounce_seconds.max(0)),
+        parent_id: Set(loc.parent_id),
+        kind: Set(loc.kind),
     };
     if Locations::insert(location).exec(db).await.is_ok() {
     let resp: Response<String> = Response::from_success("Location successfully added");
@@ -92,66 +124,147 @@ pub async fn store(db: web::Data<DB>, loc: web::Json<locations::Model>) -> impl
     }
 }
 
-// Get location name from ID
+// Get location from ID, with its current occupancy
 #[get("/api/locations/{location_id}")]
-pub async fn show(db: web::Data<DB>, id: web::Path<Id>) -> impl Responder {
+pub async fn show(
+    db: web::Data<DB>,
+    id: web::Path<Id>,
+) -> Result<HttpResponse, Box<dyn std::error::Error>> {
     let db = &db.0;
     let id = id.into_inner().location_id;
     log::info!("GET: Locations Show: {}", id);
-    if let Some(location) = Locations::find_by_id(id as i32).one(db).await.unwrap() {
-        let response: Response<entity::locations::Model> = Response::from(location);
-        HttpResponse::Ok()
+    if let Some(view) = location_views(db, Some(id as i32)).await?.pop() {
+        let response: Response<LocationView> = Response::from(view);
+        Ok(HttpResponse::Ok()
             .insert_header(ContentType::json())
-            .json(response)
+            .json(response))
     } else {
         let response = Response::<String>::from_error("Error retrieving location");
-        HttpResponse::Ok()
+        Ok(HttpResponse::Ok()
             .insert_header(ContentType::json())
-            .json(response)
+            .json(response))
     }
 }
 
+// update a location's name, level or capacity settings
+#[rustfmt::skip]
+#[patch("/api/locations/{location_id}")]
+pub async fn update(db: web::Data<DB>, id: web::Path<Id>, loc: web::Json<UpdateLocation>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let id = id.into_inner().location_id as i32;
+    let loc = loc.into_inner();
+    let Some(location) = Locations::find_by_id(id).one(db).await? else {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Error retrieving location")));
+    };
+    if let Err(message) = loc.validate() {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error(&message)));
+    }
+    let mut to_update = location.into_active_model();
+    if let Some(name) = loc.name {
+        to_update.name = Set(name);
+    }
+    if let Some(level) = loc.level {
+        to_update.level = Set(level);
+    }
+    if let Some(capacity) = loc.capacity {
+        to_update.capacity = Set(capacity);
+    }
+    if let Some(refuse) = loc.refuse_over_capacity {
+        to_update.refuse_over_capacity = Set(refuse);
+    }
+    if let Some(refuse) = loc.refuse_when_closed {
+        to_update.refuse_when_closed = Set(refuse);
+    }
+    if let Some(seconds) = loc.debounce_seconds {
+        to_update.debounce_seconds = Set(seconds.max(0));
+    }
+    if let Some(kind) = loc.kind {
+        to_update.kind = Set(kind);
+    }
+    if let Some(parent_id) = loc.parent_id {
+        // a location can't be moved underneath itself
+        let all = Locations::find().all(db).await?;
+        if let Some(parent) = parent_id {
+            if !all.iter().any(|l| l.id == parent) || descendants_of(&all, id).contains(&parent) {
+                return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Invalid parent location")));
+            }
+        }
+        to_update.parent_id = Set(parent_id);
+    }
+    // the settings and the caps they go with are applied together or not at all
+    let txn = db.begin().await?;
+    if to_update.is_changed() {
+        to_update.update(&txn).await?;
+    }
+    if let Some(caps) = loc.level_caps {
+        LocationLevelCaps::delete_many().filter(location_level_caps::Column::LocationId.eq(id)).exec(&txn).await?;
+        if !caps.is_empty() {
+            LocationLevelCaps::insert_many(caps.into_iter().map(|cap| location_level_caps::ActiveModel {
+                location_id: Set(id),
+                level: Set(cap.level),
+                capacity: Set(cap.capacity),
+                ..Default::default()
+            })).exec(&txn).await?;
+        }
+    }
+    txn.commit().await?;
+    let view = location_views(db, Some(id)).await?.pop().ok_or("Error retrieving location")?;
+    let response: Response<LocationView> = Response::from(view);
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+}
+
 // include range in url to show timestamps from /start/end
 #[rustfmt::skip]
 #[get("/api/locations/{location_id}/timestamps/{start_date}/{end_date}")]
-pub async fn show_location_timestamps_range(db: web::Data<DB>, id: web::Path<LocationRange>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+pub async fn show_location_timestamps_range(db: web::Data<DB>, id: web::Path<LocationRange>, params: web::Query<Params>, shift: web::Query<ShiftParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
     let db = &db.0;
     let loc_range = id.into_inner();
-    let timestamps = Timestamps::find().filter(
-        timestamps::Column::Location
-            .eq(loc_range.location_id as i32)
-            .and(timestamps::Column::Ts.between(loc_range.start_date.and_hms_opt(0, 0, 0), loc_range.end_date.and_hms_opt(23, 59, 59))),
-    ).order_by_desc(timestamps::Column::Ts).all(db).await?;
+    let ids = scope(db, loc_range.location_id, params.into_inner().rollup).await?;
+    let shift = match shift.into_inner().shift {
+        Some(name) => match resolve_shift(db, &name, Local::now().naive_local()).await? {
+            Some(shift) => Some(shift),
+            None => return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::shift_not_found())),
+        },
+        None => None,
+    };
+    let timestamps = timestamps_between(loc_range.start_date.and_hms_opt(0, 0, 0), loc_range.end_date.and_hms_opt(23, 59, 59), shift.as_ref())
+        .filter(timestamps::Column::Location.is_in(ids))
+        .order_by_desc(timestamps::Column::Ts)
+        .all(db)
+        .await?;
     let response: Response<timestamps::Model> = Response::from(timestamps);
     Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
 }
 
-// show timestamps from today for a location
+// show timestamps from the current (or ?shift=) shift for a location
 #[rustfmt::skip]
 #[get("/api/locations/{location_id}/timestamps")]
-pub async fn show_location_timestamps(db: web::Data<DB>, id: web::Path<Id>, uni: web::Query<FilterOpts>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+pub async fn show_location_timestamps(db: web::Data<DB>, id: web::Path<Id>, uni: web::Query<FilterOpts>, shift: web::Query<ShiftParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
     let db = &db.0;
     let id = id.into_inner().location_id;
+    let uni = uni.into_inner();
+    let Some((start, end)) = shift_window(db, shift.shift.as_deref(), Local::now().naive_local()).await? else {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::shift_not_found()));
+    };
+    let ids = scope(db, id, uni.rollup).await?;
     let mut hshset = std::collections::HashSet::new();
-    let result: Vec<timestamps::Model> = Timestamps::find()
-        .filter(
-            timestamps::Column::Location.eq(id as i32)
-                .and(timestamps::Column::Ts.between(chrono::offset::Local::now().checked_sub_days(Days::new(1)), Some(chrono::offset::Local::now())))
-        ).order_by_desc(timestamps::Column::Ts)
+    let result: Vec<timestamps::Model> = timestamps_within(start, end)
+        .filter(timestamps::Column::Location.is_in(ids))
+        .order_by_desc(timestamps::Column::Ts)
         .all(db)
         .await?;
         let mut res = Vec::new();
-    if let Some(true) = uni.into_inner().unique {
+    if let Some(true) = uni.unique {
         for ts in result {
-            if hshset.insert(ts.rfid.clone()) {
+            if hshset.insert(ts.rfid) {
            res.push(ts);
             } else {
                 continue;
             }
         }
-        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<timestamps::Model>::from(res)));
+        Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<timestamps::Model>::from(res)))
     } else {
-       return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::from(result)));
+       Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::from(result)))
     }
 }
 
@@ -161,19 +274,34 @@ pub async fn show_location_timestamps(db: web::Data<DB>, id: web::Path<Id>, uni:
 pub async fn show_location_residents(db: web::Data<DB>, id: web::Path<Id>, curr: web::Query<Params>) -> Result<HttpResponse, Box<dyn std::error::Error>>  {
     let db = &db.0;
     let id = id.into_inner().location_id;
-    if curr.into_inner().current.is_some_and(|c| c) {
+    let curr = curr.into_inner();
+    let ids = scope(db, id, curr.rollup).await?;
+    if curr.current.is_some_and(|c| c) {
         let residents: Vec<residents::Model> = Residents::find()
-            .filter(residents::Column::CurrentLocation.eq(id as i32))
+            .filter(residents::Column::CurrentLocation.is_in(ids))
             .all(db)
             .await?;
         let response: Response<residents::Model> = Response::from(residents);
         Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
     } else {
         let residents: Vec<residents::Model> = Residents::find()
-            .filter(residents::Column::Unit.eq(id as i32))
+            .filter(residents::Column::Unit.is_in(ids))
             .all(db)
             .await?;
         let response: Response<residents::Model> = Response::from(residents);
         Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
     }
 }
+
+// reconstruct who was at a location at a given time from the timestamp history
+#[rustfmt::skip]
+#[get("/api/locations/{location_id}/occupants")]
+pub async fn show_location_occupants(db: web::Data<DB>, id: web::Path<Id>, at: web::Query<AtParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let id = id.into_inner().location_id;
+    let at = at.into_inner();
+    let ids = scope(db, id, at.rollup).await?;
+    let occupants = occupants_at(db, at.at, Some(&ids)).await?;
+    let response: Response<Occupant> = Response::from(occupants);
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+}
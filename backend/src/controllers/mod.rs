@@ -3,3 +3,25 @@ pub mod residents_controller;
 pub mod locations_controller;
 
 pub mod timestamps_controller;
+
+pub mod separations_controller;
+
+pub mod alerts_controller;
+
+pub mod schedules_controller;
+
+pub mod appointments_controller;
+
+pub mod reports_controller;
+
+pub mod shifts_controller;
+
+pub mod admin_controller;
+
+pub mod webhooks_controller;
+
+pub mod rules_controller;
+
+pub mod photos_controller;
+
+pub mod notes_controller;
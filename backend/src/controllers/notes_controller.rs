@@ -0,0 +1,109 @@
+use crate::app_config::DB;
+use crate::models::{
+    notes::{notes_for, validate, NewNote, NoteParams, UpdateNote},
+    residents::{find_resident, ResidentKey, Rfid},
+    response::Response,
+};
+use actix_web::{delete, get, http::header::ContentType, patch, post, web, HttpResponse};
+use chrono::Local;
+use entity::resident_notes::{self, Entity as ResidentNotes};
+use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel, ModelTrait, NotSet};
+
+/// GET: /api/residents/{rfid}/notes?include_expired=true&severity=high  Newest first
+#[rustfmt::skip]
+#[get("/api/residents/{rfid}/notes")]
+pub async fn index(db: web::Data<DB>, rfid: web::Path<Rfid>, params: web::Query<NoteParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let Some(resident) = find_resident(db, &ResidentKey::Rfid(rfid.into_inner().rfid)).await? else {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::resident_not_found()));
+    };
+    let notes = notes_for(db, resident.id, &params.into_inner(), Local::now().naive_local()).await?;
+    let response: Response<resident_notes::Model> = Response::from(notes);
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+}
+
+#[rustfmt::skip]
+#[post("/api/residents/{rfid}/notes")]
+pub async fn store(db: web::Data<DB>, rfid: web::Path<Rfid>, note: web::Json<NewNote>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let Some(resident) = find_resident(db, &ResidentKey::Rfid(rfid.into_inner().rfid)).await? else {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::resident_not_found()));
+    };
+    let note = note.into_inner();
+    let now = Local::now().naive_local();
+    let note = resident_notes::Model {
+        id: 0,
+        resident_id: resident.id,
+        author: note.author,
+        category: note.category,
+        note: note.note,
+        severity: note.severity,
+        expires_at: note.expires_at,
+        created_at: now,
+    };
+    if let Err(message) = validate(&note) {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error(&message)));
+    }
+    if note.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("A new note can't have expired already")));
+    }
+    let mut new_note = note.into_active_model().reset_all();
+    new_note.id = NotSet;
+    let response: Response<resident_notes::Model> = Response::from(new_note.insert(db).await?);
+    Ok(HttpResponse::Created().insert_header(ContentType::json()).json(response))
+}
+
+#[rustfmt::skip]
+#[get("/api/notes/{id}")]
+pub async fn show(db: web::Data<DB>, id: web::Path<i32>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    match ResidentNotes::find_by_id(id.into_inner()).one(db).await? {
+        Some(note) => {
+            let response: Response<resident_notes::Model> = Response::from(note);
+            Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+        }
+        None => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::note_not_found())),
+    }
+}
+
+// setting expires_at to now or earlier ends a note but keeps it on record
+#[rustfmt::skip]
+#[patch("/api/notes/{id}")]
+pub async fn update(db: web::Data<DB>, id: web::Path<i32>, note: web::Json<UpdateNote>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let changes = note.into_inner();
+    let Some(mut note) = ResidentNotes::find_by_id(id.into_inner()).one(db).await? else {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::note_not_found()));
+    };
+    if let Some(category) = changes.category {
+        note.category = category;
+    }
+    if let Some(text) = changes.note {
+        note.note = text;
+    }
+    if let Some(severity) = changes.severity {
+        note.severity = severity;
+    }
+    if let Some(expires_at) = changes.expires_at {
+        note.expires_at = expires_at;
+    }
+    if let Err(message) = validate(&note) {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error(&message)));
+    }
+    let to_update = note.into_active_model().reset_all();
+    let response: Response<resident_notes::Model> = Response::from(to_update.update(db).await?);
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+}
+
+#[rustfmt::skip]
+#[delete("/api/notes/{id}")]
+pub async fn destroy(db: web::Data<DB>, id: web::Path<i32>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    match ResidentNotes::find_by_id(id.into_inner()).one(db).await? {
+        Some(note) => {
+            note.delete(db).await?;
+            Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_success("Note successfully removed")))
+        }
+        None => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::note_not_found())),
+    }
+}
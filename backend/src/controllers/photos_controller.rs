@@ -0,0 +1,107 @@
+use crate::app_config::DB;
+use crate::models::{badges, residents::Rfid, response::Response};
+use crate::photos::{self, storage::PhotoStorage, Format};
+use actix_web::{
+    get,
+    http::header::{CacheControl, CacheDirective, ContentType},
+    web, HttpResponse,
+};
+use entity::residents::{self, Entity as Resident};
+use sea_orm::{ActiveModelTrait, EntityTrait, QueryFilter, Set};
+
+/// Drop the files behind a resident's old photo URLs. A file that won't go is
+/// only logged, the resident has already moved on to the new ones.
+pub(crate) async fn remove_files(storage: &web::Data<dyn PhotoStorage>, urls: [Option<String>; 2]) {
+    for key in urls.iter().flatten().filter_map(|url| photos::key_of(url)) {
+        let key = key.to_string();
+        let storage = storage.clone();
+        match web::block(move || storage.delete(&key)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => log::warn!("failed to remove photo file: {}", err),
+            Err(err) => log::warn!("failed to remove photo file: {}", err),
+        }
+    }
+}
+
+/// PUT: /api/residents/{rfid}/photo  Body is the PNG or JPEG itself; replaces any earlier photo
+#[rustfmt::skip]
+pub async fn upload(db: web::Data<DB>, storage: web::Data<dyn PhotoStorage>, rfid: web::Path<Rfid>, body: web::Bytes) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let rfid = rfid.into_inner().rfid;
+    let Some(resident) = Resident::find().filter(badges::matches(&rfid)).one(db).await? else {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::resident_not_found()));
+    };
+    // decoding a few megapixels is too slow for the async workers
+    let upload = body.clone();
+    let prepared = match web::block(move || photos::prepare(&upload)).await? {
+        Ok(prepared) => prepared,
+        Err(err) => return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error(&err.to_string()))),
+    };
+    let (photo_key, thumb_key) = photos::keys_for(resident.id, prepared.format);
+    let files = storage.clone();
+    let (photo, thumb) = (photo_key.clone(), thumb_key.clone());
+    web::block(move || {
+        files.put(&photo, &body)?;
+        files.put(&thumb, &prepared.thumbnail)
+    }).await??;
+    let updated = residents::ActiveModel {
+        id: Set(resident.id),
+        photo_url: Set(Some(photos::url_for(&photo_key))),
+        thumbnail_url: Set(Some(photos::url_for(&thumb_key))),
+        ..Default::default()
+    }.update(db).await;
+    match updated {
+        Ok(updated) => {
+            remove_files(&storage, [resident.photo_url, resident.thumbnail_url]).await;
+            let response: Response<residents::Model> = Response::from(updated);
+            Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+        }
+        Err(err) => {
+            remove_files(&storage, [Some(photos::url_for(&photo_key)), Some(photos::url_for(&thumb_key))]).await;
+            Err(err.into())
+        }
+    }
+}
+
+/// DELETE: /api/residents/{rfid}/photo
+#[rustfmt::skip]
+pub async fn destroy(db: web::Data<DB>, storage: web::Data<dyn PhotoStorage>, rfid: web::Path<Rfid>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let rfid = rfid.into_inner().rfid;
+    let Some(resident) = Resident::find().filter(badges::matches(&rfid)).one(db).await? else {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::resident_not_found()));
+    };
+    if resident.photo_url.is_none() && resident.thumbnail_url.is_none() {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Resident has no photo")));
+    }
+    let updated = residents::ActiveModel {
+        id: Set(resident.id),
+        photo_url: Set(None),
+        thumbnail_url: Set(None),
+        ..Default::default()
+    }.update(db).await?;
+    remove_files(&storage, [resident.photo_url, resident.thumbnail_url]).await;
+    let response: Response<residents::Model> = Response::from(updated);
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+}
+
+/// GET: /api/photos/{key}  The image itself, so a 404 rather than an error body an <img> can't show
+#[rustfmt::skip]
+#[get("/api/photos/{key}")]
+pub async fn show(storage: web::Data<dyn PhotoStorage>, key: web::Path<String>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let key = key.into_inner();
+    let content_type = Format::content_type(&key);
+    let stored = match web::block(move || storage.get(&key)).await? {
+        Ok(stored) => stored,
+        Err(err) if err.kind() == std::io::ErrorKind::InvalidInput => None,
+        Err(err) => return Err(err.into()),
+    };
+    match stored {
+        // keys are never reused, so a photo can be cached for as long as the browser likes
+        Some(bytes) => Ok(HttpResponse::Ok()
+            .content_type(content_type)
+            .insert_header(CacheControl(vec![CacheDirective::Private, CacheDirective::MaxAge(31_536_000), CacheDirective::Extension("immutable".to_string(), None)]))
+            .body(bytes)),
+        None => Ok(HttpResponse::NotFound().insert_header(ContentType::json()).json(Response::<String>::from_error("Photo not found"))),
+    }
+}
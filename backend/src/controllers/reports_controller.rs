@@ -0,0 +1,54 @@
+use crate::app_config::DB;
+use crate::models::{
+    response::Response,
+    shifts::{latest_run, resolve_shift, run_on},
+};
+use crate::reports::movement_log::MovementLog;
+use actix_web::{get, http::header::ContentType, web, HttpResponse};
+use chrono::{Duration, Local, NaiveDate};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct ReportParams {
+    /// defaults to today, or the latest run of the shift
+    pub date: Option<NaiveDate>,
+    /// the whole day when not given
+    pub shift: Option<String>,
+    /// `html` (default) or `pdf`
+    pub format: Option<String>,
+}
+
+/// GET: /api/reports/movement-log?date=2023-11-21&shift=day&format=pdf
+#[rustfmt::skip]
+#[get("/api/reports/movement-log")]
+pub async fn movement_log(db: web::Data<DB>, params: web::Query<ReportParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let params = params.into_inner();
+    let now = Local::now().naive_local();
+    let (title, from, to) = match params.shift {
+        Some(name) => {
+            let Some(shift) = resolve_shift(db, &name, now).await? else {
+                return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::shift_not_found()));
+            };
+            // without a date, the latest run of the shift, so last night's shift after midnight
+            let run = match params.date {
+                Some(date) => run_on(&shift, date),
+                None => latest_run(&shift, now),
+            };
+            (format!("Movement log: {} shift {}", shift.name, run.start.date()), run.start, run.end)
+        }
+        None => {
+            let from = params.date.unwrap_or(now.date()).and_hms_opt(0, 0, 0).unwrap();
+            (format!("Movement log: {}", from.date()), from, from + Duration::days(1))
+        }
+    };
+    let log = MovementLog::build(db, title, from, to).await?;
+    match params.format.as_deref() {
+        None | Some("html") => Ok(HttpResponse::Ok().insert_header(ContentType::html()).body(log.to_html())),
+        Some("pdf") => Ok(HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header(("Content-Disposition", format!("inline; filename=\"movement-log-{}.pdf\"", from.date())))
+            .body(log.to_pdf())),
+        Some(_) => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Format must be html or pdf"))),
+    }
+}
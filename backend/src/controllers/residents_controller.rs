@@ -1,21 +1,35 @@
 use crate::app_config::DB;
-use crate::models::residents::UpdateResident;
+use crate::controllers::photos_controller::remove_files;
+use crate::models::away::{away_residents, AwayParams, AwayResident};
+use crate::models::contacts::{contacts_for, Contact, ContactParams};
+use crate::models::residents::{
+    conflict, find_resident, normalize_doc, save_resident, ResidentKey, UpdateResident,
+};
+use crate::models::search::{search as search_residents, ResidentMatch, SearchParams};
+use crate::models::shifts::{resolve_shift, shift_window, ShiftParams};
+use crate::models::timestamps::{timestamps_between, timestamps_within};
 use crate::models::{
+    badges::{self, canonical},
     residents::{PathParams, Rfid},
     response::Response,
+    webhooks,
 };
+use crate::photos::storage::PhotoStorage;
 use actix_web::Responder;
 use actix_web::{
     delete, get,
     http::{header, StatusCode},
     patch, post, web, HttpResponse,
 };
-use chrono::Days;
+use chrono::{Duration, Local};
 use entity::{
     residents::{self, Entity as Resident},
     timestamps,
 };
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, ModelTrait, QueryFilter,
+    Set, TransactionTrait,
+};
 
 #[get("/api/residents")]
 pub async fn index(db: web::Data<DB>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
@@ -30,24 +44,89 @@ pub async fn index(db: web::Data<DB>) -> Result<HttpResponse, Box<dyn std::error
     }
 }
 
+/// GET: /api/residents/away?min_minutes=30&unit=6  Longest gone first
 #[rustfmt::skip]
-#[get("/api/residents/{rfid}")]
-pub async fn show(db: web::Data<DB>, rfid: actix_web::web::Path<Rfid>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+#[get("/api/residents/away")]
+pub async fn away(db: web::Data<DB>, params: web::Query<AwayParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
     let db = &db.0;
-    let rfid = rfid.into_inner().rfid;
-    if let Ok(resident) = Resident::find().filter(residents::Column::Rfid.eq(rfid.clone())).one(db).await {
-    if resident.is_none() {
-        let error = Response::<String>::from_error("Error retrieving residents");
-        return Ok(HttpResponse::Ok()
-            .insert_header(header::ContentType::json())
-            .json(error));
+    let away = away_residents(db, &params.into_inner(), Local::now().naive_local()).await?;
+    let response: Response<AwayResident> = Response::from(away);
+    Ok(HttpResponse::Ok().insert_header(header::ContentType::json()).json(response))
+}
+
+/// GET: /api/residents/search?q=doe, john&limit=20  Best match first
+#[rustfmt::skip]
+#[get("/api/residents/search")]
+pub async fn search(db: web::Data<DB>, params: web::Query<SearchParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let params = params.into_inner();
+    if params.q.trim().is_empty() {
+        return Ok(HttpResponse::Ok().insert_header(header::ContentType::json()).json(Response::<String>::from_error("Error: 'q' must not be empty")));
     }
-    let response: Response<residents::Model> = Response::from(resident.unwrap());
+    let matches = search_residents(db, &params).await?;
+    let response: Response<ResidentMatch> = Response::from(matches);
     Ok(HttpResponse::Ok().insert_header(header::ContentType::json()).json(response))
-    } else {
-        let response = Response::<String>::from_error("Error retrieving residents");
-        Ok(HttpResponse::Ok().insert_header(header::ContentType::json()).json(response))
+}
+
+/// The resident `key` names, or a not found response
+#[rustfmt::skip]
+async fn show_by(db: &DatabaseConnection, key: ResidentKey) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    match find_resident(db, &key).await? {
+        Some(resident) => {
+            let response: Response<residents::Model> = Response::from(resident);
+            Ok(HttpResponse::Ok().insert_header(header::ContentType::json()).json(response))
+        }
+        None => Ok(HttpResponse::Ok().insert_header(header::ContentType::json()).json(Response::<String>::resident_not_found())),
+    }
+}
+
+/// Apply `changes` to the resident `key` names
+#[rustfmt::skip]
+async fn update_by(db: &DatabaseConnection, key: ResidentKey, mut changes: UpdateResident) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    if let Some(new_rfid) = changes.rfid {
+        match canonical(&new_rfid) {
+            Ok(new_rfid) => changes.rfid = Some(new_rfid),
+            Err(err) => return Ok(HttpResponse::Ok().insert_header(header::ContentType::json()).json(Response::<String>::from_error(&err.to_string()))),
+        }
     }
+    let Some(resident) = find_resident(db, &key).await? else {
+        return Ok(HttpResponse::Ok().insert_header(header::ContentType::json()).json(Response::<String>::resident_not_found()));
+    };
+    let response = match save_resident(db, changes.apply(resident)).await? {
+        Ok(_) => Response::<String>::from_success("Resident Updated Successfully"),
+        Err(conflict) => Response::<String>::from_error(&conflict),
+    };
+    Ok(HttpResponse::Ok().insert_header(header::ContentType::json()).json(response))
+}
+
+#[rustfmt::skip]
+#[get("/api/residents/by-doc/{doc}")]
+pub async fn show_by_doc(db: web::Data<DB>, doc: web::Path<String>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    show_by(&db.0, ResidentKey::Doc(doc.into_inner())).await
+}
+
+#[rustfmt::skip]
+#[patch("/api/residents/by-doc/{doc}")]
+pub async fn update_by_doc(db: web::Data<DB>, doc: web::Path<String>, resident: web::Json<UpdateResident>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    update_by(&db.0, ResidentKey::Doc(doc.into_inner()), resident.into_inner()).await
+}
+
+#[rustfmt::skip]
+#[get("/api/residents/by-id/{id}")]
+pub async fn show_by_id(db: web::Data<DB>, id: web::Path<i32>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    show_by(&db.0, ResidentKey::Id(id.into_inner())).await
+}
+
+#[rustfmt::skip]
+#[patch("/api/residents/by-id/{id}")]
+pub async fn update_by_id(db: web::Data<DB>, id: web::Path<i32>, resident: web::Json<UpdateResident>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    update_by(&db.0, ResidentKey::Id(id.into_inner()), resident.into_inner()).await
+}
+
+#[rustfmt::skip]
+#[get("/api/residents/{rfid}")]
+pub async fn show(db: web::Data<DB>, rfid: actix_web::web::Path<Rfid>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    show_by(&db.0, ResidentKey::Rfid(rfid.into_inner().rfid)).await
 }
 
 #[rustfmt::skip]
@@ -55,78 +134,81 @@ pub async fn show(db: web::Data<DB>, rfid: actix_web::web::Path<Rfid>) -> Result
 pub async fn store(db: web::Data<DB>, resident: web::Json<residents::Model>) -> impl Responder {
     let db = &db.0;
     let resident = resident.into_inner();
+    let rfid = match canonical(&resident.rfid) {
+        Ok(rfid) => rfid,
+        Err(err) => return HttpResponse::Ok().insert_header(header::ContentType::json()).json(Response::<String>::from_error(&err.to_string())),
+    };
+    let doc = normalize_doc(&resident.doc);
     let resident = residents::ActiveModel {
-        rfid: Set(resident.rfid),
+        rfid: Set(rfid),
         name: Set(resident.name),
-        doc: Set(resident.doc),
+        doc: Set(doc.clone()),
         room: Set(resident.room),
         unit: Set(resident.unit),
         current_location: Set(resident.current_location),
         level: Set(resident.level),
         ..Default::default()
     };
-    if  Resident::insert(resident).exec(db).await.is_ok() {
-        HttpResponse::Ok().insert_header(header::ContentType::json()).json(Response::<String>::from_success("Resident successfully added"))
-    } else {
-    HttpResponse::Ok().insert_header(header::ContentType::json()).json(Response::<String>::from_error("Error adding resident"))
+    let created: Result<(), DbErr> = async {
+        // the resident_created event is queued in the same transaction
+        let txn = db.begin().await?;
+        let created = resident.insert(&txn).await?;
+        webhooks::enqueue(&txn, webhooks::RESIDENT_CREATED, &created).await?;
+        txn.commit().await
+    }.await;
+    match created {
+        Ok(_) => HttpResponse::Ok().insert_header(header::ContentType::json()).json(Response::<String>::from_success("Resident successfully added")),
+        Err(err) => {
+            let message = conflict(&err, &doc).unwrap_or("Error adding resident".to_string());
+            HttpResponse::Ok().insert_header(header::ContentType::json()).json(Response::<String>::from_error(&message))
+        }
     }
 }
 
 #[rustfmt::skip]
 #[delete("/api/residents/{rfid}")]
-pub async fn destroy(db: web::Data<DB>, rfid: web::Path<String>,) -> impl Responder {
+pub async fn destroy(db: web::Data<DB>, storage: web::Data<dyn PhotoStorage>, rfid: web::Path<String>,) -> impl Responder {
     let db = &db.0;
     let rfid = rfid.into_inner();
-    if let Ok(resident) = Resident::find().filter(residents::Column::Rfid.eq(rfid.clone())).one(db).await {
-    let resident: residents::ActiveModel = resident.unwrap().into();
-    match resident.delete(db).await {
-        Ok(_) => 
-    HttpResponse::Ok().status(StatusCode::NO_CONTENT).body(format!("Deleted resident: {}", rfid)),
+    let resident = match find_resident(db, &ResidentKey::Rfid(rfid.clone())).await {
+        Ok(Some(resident)) => resident,
+        Ok(None) => return HttpResponse::Ok().insert_header(header::ContentType::json()).json(Response::<String>::resident_not_found()),
+        Err(_) => return HttpResponse::Ok().insert_header(header::ContentType::json()).json(Response::<String>::from_error("Error deleting resident")),
+    };
+    let released: Result<(), DbErr> = async {
+        let txn = db.begin().await?;
+        resident.clone().delete(&txn).await?;
+        webhooks::enqueue(&txn, webhooks::RESIDENT_RELEASED, &resident).await?;
+        txn.commit().await
+    }.await;
+    match released {
+        Ok(_) => {
+    remove_files(&storage, [resident.photo_url, resident.thumbnail_url]).await;
+    HttpResponse::Ok().status(StatusCode::NO_CONTENT).body(format!("Deleted resident: {}", rfid))
+        }
     Err(e) => HttpResponse::Ok().body(format!("Error deleting resident: {}", e))
     }
-    } else {
-        HttpResponse::Ok().insert_header(header::ContentType::json()).json(Response::<String>::from_error("Error deleting resident"))
-    }
 }
 
 #[rustfmt::skip]
 #[patch("/api/residents/{rfid}")]
 pub async fn update(db: web::Data<DB>, rfid: actix_web::web::Path<Rfid>, resident: web::Json<UpdateResident>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
-     let db = &db.0;
-    let rfid = rfid.into_inner().rfid;
-    let resident = resident.into_inner();
-    if let Ok(to_update) = Resident::find().filter(residents::Column::Rfid.eq(rfid.clone())).one(db).await {
-    if to_update.is_none() {
-        let error = Response::<String>::from_error("Error retrieving resident");
-        return Ok(HttpResponse::Ok()
-            .insert_header(header::ContentType::json())
-            .json(error));
-    } else {
-        let mut to_update: residents::ActiveModel = to_update.unwrap().into();
-        to_update.rfid = Set(resident.rfid.unwrap_or_else(|| to_update.rfid.unwrap()));
-        to_update.name = Set(resident.name.unwrap_or_else(|| to_update.name.unwrap()));
-        to_update.room = Set(resident.room.unwrap_or_else(|| to_update.room.unwrap()));
-        to_update.unit = Set(resident.unit.unwrap_or_else(|| to_update.unit.unwrap() as usize) as i32);
-        to_update.current_location = Set(resident.current_location.unwrap_or_else(|| to_update.current_location.unwrap() as usize) as i32);
-        to_update.level = Set(resident.level.unwrap_or_else(|| to_update.level.unwrap() as usize) as i32);
-        to_update.save(db).await?;
-        let response: Response<String> = Response::from_success("Resident Updated Successfully");
-        Ok(HttpResponse::Ok().insert_header(header::ContentType::json()).json(response))
-    }
-
-} else {
-        Ok(HttpResponse::Ok().body("Error updating resident"))
-    }
+    update_by(&db.0, ResidentKey::Rfid(rfid.into_inner().rfid), resident.into_inner()).await
 }
 
 #[rustfmt::skip]
 #[get("/api/residents/{rfid}/timestamps")]
-pub async fn show_resident_timestamps(db: web::Data<DB>, rfid: actix_web::web::Path<Rfid>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+pub async fn show_resident_timestamps(db: web::Data<DB>, rfid: actix_web::web::Path<Rfid>, shift: web::Query<ShiftParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
     let db = &db.0;
     let rfid = rfid.into_inner().rfid;
-    if let Ok(ts) = timestamps::Entity::find()
-        .filter(timestamps::Column::Rfid.contains(rfid))
-        .filter(timestamps::Column::Ts.between(chrono::Local::now().checked_sub_days(Days::new(1)), Some(chrono::Local::now())))
+    let Some((start, end)) = shift_window(db, shift.shift.as_deref(), chrono::Local::now().naive_local()).await? else {
+        return Ok(HttpResponse::Ok().insert_header(header::ContentType::json()).json(Response::<String>::shift_not_found()));
+    };
+    let Some(resident) = Resident::find().filter(badges::matches(&rfid)).one(db).await? else {
+        return Ok(HttpResponse::Ok().insert_header(header::ContentType::json()).json(Response::<String>::resident_not_found()));
+    };
+    if let Ok(ts) = timestamps_within(start, end)
+        .filter(timestamps::Column::Rfid.eq(resident.id))
         .all(db).await {
     let response: Response<timestamps::Model> = Response::from(ts);
     Ok(HttpResponse::Ok().insert_header(header::ContentType::json()).json(response))
@@ -138,19 +220,49 @@ pub async fn show_resident_timestamps(db: web::Data<DB>, rfid: actix_web::web::P
 
 #[rustfmt::skip]
 #[get("/api/residents/{rfid}/timestamps/{start_date}/{end_date}")]
-pub async fn show_resident_timestamps_range(db: web::Data<DB>, rfid: actix_web::web::Path<PathParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+pub async fn show_resident_timestamps_range(db: web::Data<DB>, rfid: actix_web::web::Path<PathParams>, shift: web::Query<ShiftParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
     let db = &db.0;
     let id = rfid.into_inner();
     let rfid = id.rfid;
     let start = id.start_date;
     let end = id.end_date;
-    if let Ok(ts) = timestamps::Entity::find()
-        .filter(timestamps::Column::Rfid.contains(rfid))
-        .filter(timestamps::Column::Ts.between(start, end))
-        .all(db).await {
+    let Some(resident) = Resident::find().filter(badges::matches(&rfid)).one(db).await? else {
+        return Ok(HttpResponse::Ok().insert_header(header::ContentType::json()).json(Response::<String>::resident_not_found()));
+    };
+    let shift = match shift.into_inner().shift {
+        Some(name) => match resolve_shift(db, &name, chrono::Local::now().naive_local()).await? {
+            Some(shift) => Some(shift),
+            None => return Ok(HttpResponse::Ok().insert_header(header::ContentType::json()).json(Response::<String>::shift_not_found())),
+        },
+        None => None,
+    };
+    let query = timestamps_between(start, end, shift.as_ref())
+        .filter(timestamps::Column::Rfid.eq(resident.id));
+    if let Ok(ts) = query.all(db).await {
 let response: Response<timestamps::Model> = Response::from(ts);
     Ok(HttpResponse::Ok().insert_header(header::ContentType::json()).json(response))
     } else {
         Ok(HttpResponse::Ok().body("Error retrieving timestamps"))
     }
 }
+
+#[rustfmt::skip]
+#[get("/api/residents/{rfid}/contacts")]
+pub async fn show_resident_contacts(db: web::Data<DB>, rfid: actix_web::web::Path<Rfid>, params: web::Query<ContactParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let rfid = rfid.into_inner().rfid;
+    let params = params.into_inner();
+    if params.to <= params.from {
+        let response = Response::<String>::from_error("Error: 'to' must be after 'from'");
+        return Ok(HttpResponse::Ok().insert_header(header::ContentType::json()).json(response));
+    }
+    match Resident::find().filter(badges::matches(&rfid)).one(db).await? {
+        Some(resident) => {
+            let min_overlap = Duration::minutes(params.min_overlap.unwrap_or(0));
+            let contacts = contacts_for(db, &resident, params.from, params.to, min_overlap).await?;
+            let response: Response<Contact> = Response::from(contacts);
+            Ok(HttpResponse::Ok().insert_header(header::ContentType::json()).json(response))
+        }
+        None => Ok(HttpResponse::Ok().insert_header(header::ContentType::json()).json(Response::<String>::resident_not_found())),
+    }
+}
@@ -0,0 +1,124 @@
+use crate::app_config::DB;
+use crate::models::{
+    response::Response,
+    rules::{location_exists, validate, NewRule, UpdateRule},
+};
+use actix_web::{delete, get, http::header::ContentType, patch, post, web, HttpResponse};
+use chrono::Local;
+use entity::rules::{self, Entity as Rules};
+use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel, ModelTrait, NotSet, QueryOrder};
+
+#[rustfmt::skip]
+#[get("/api/rules")]
+pub async fn index(db: web::Data<DB>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let rules = Rules::find().order_by_asc(rules::Column::Id).all(db).await?;
+    let response: Response<rules::Model> = Response::from(rules);
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+}
+
+#[rustfmt::skip]
+#[get("/api/rules/{id}")]
+pub async fn show(db: web::Data<DB>, id: web::Path<i32>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    match Rules::find_by_id(id.into_inner()).one(db).await? {
+        Some(rule) => {
+            let response: Response<rules::Model> = Response::from(rule);
+            Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+        }
+        None => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::rule_not_found())),
+    }
+}
+
+// a new rule is first evaluated by the next sweep or the next scan it covers
+#[rustfmt::skip]
+#[post("/api/rules")]
+pub async fn store(db: web::Data<DB>, rule: web::Json<NewRule>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let rule = rule.into_inner();
+    let rule = rules::Model {
+        id: 0,
+        name: rule.name,
+        kind: rule.kind,
+        minutes: rule.minutes,
+        location: rule.location,
+        unit: rule.unit,
+        max_count: rule.max_count,
+        resident_level: rule.resident_level,
+        location_level: rule.location_level,
+        active: true,
+        created_at: Local::now().naive_local(),
+    };
+    if let Err(message) = validate(&rule) {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error(&message)));
+    }
+    if let Some(location) = rule.location {
+        if !location_exists(db, location).await? {
+            return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Location not found")));
+        }
+    }
+    let mut new_rule = rule.into_active_model().reset_all();
+    new_rule.id = NotSet;
+    let response: Response<rules::Model> = Response::from(new_rule.insert(db).await?);
+    Ok(HttpResponse::Created().insert_header(ContentType::json()).json(response))
+}
+
+#[rustfmt::skip]
+#[patch("/api/rules/{id}")]
+pub async fn update(db: web::Data<DB>, id: web::Path<i32>, rule: web::Json<UpdateRule>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let changes = rule.into_inner();
+    let Some(mut rule) = Rules::find_by_id(id.into_inner()).one(db).await? else {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::rule_not_found()));
+    };
+    let previous_location = rule.location;
+    if let Some(name) = changes.name {
+        rule.name = name;
+    }
+    if let Some(minutes) = changes.minutes {
+        rule.minutes = minutes;
+    }
+    if let Some(location) = changes.location {
+        rule.location = location;
+    }
+    if let Some(unit) = changes.unit {
+        rule.unit = unit;
+    }
+    if let Some(max_count) = changes.max_count {
+        rule.max_count = max_count;
+    }
+    if let Some(resident_level) = changes.resident_level {
+        rule.resident_level = resident_level;
+    }
+    if let Some(location_level) = changes.location_level {
+        rule.location_level = location_level;
+    }
+    if let Some(active) = changes.active {
+        rule.active = active;
+    }
+    if let Err(message) = validate(&rule) {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error(&message)));
+    }
+    if let Some(location) = rule.location.filter(|l| Some(*l) != previous_location) {
+        if !location_exists(db, location).await? {
+            return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Location not found")));
+        }
+    }
+    let to_update = rule.into_active_model().reset_all();
+    let response: Response<rules::Model> = Response::from(to_update.update(db).await?);
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+}
+
+// alerts the rule raised stay, without the link back to it
+#[rustfmt::skip]
+#[delete("/api/rules/{id}")]
+pub async fn destroy(db: web::Data<DB>, id: web::Path<i32>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    match Rules::find_by_id(id.into_inner()).one(db).await? {
+        Some(rule) => {
+            rule.delete(db).await?;
+            Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_success("Rule successfully removed")))
+        }
+        None => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::rule_not_found())),
+    }
+}
@@ -0,0 +1,122 @@
+use super::locations_controller::Id;
+use crate::app_config::DB;
+use crate::models::{
+    response::Response,
+    schedules::{valid_hours, valid_weekday, NewSchedule, UpdateSchedule},
+};
+use actix_web::{delete, get, http::header::ContentType, patch, post, web, HttpResponse};
+use entity::{
+    location_schedules::{self, Entity as LocationSchedules},
+    locations::Entity as Locations,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, ModelTrait, QueryFilter,
+    QueryOrder, Set, TryIntoModel,
+};
+
+// all schedules for a location, by weekday
+#[rustfmt::skip]
+#[get("/api/locations/{location_id}/schedules")]
+pub async fn index(db: web::Data<DB>, id: web::Path<Id>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let id = id.into_inner().location_id as i32;
+    let schedules = LocationSchedules::find()
+        .filter(location_schedules::Column::LocationId.eq(id))
+        .order_by_asc(location_schedules::Column::Weekday)
+        .order_by_asc(location_schedules::Column::OpensAt)
+        .all(db)
+        .await?;
+    let response: Response<location_schedules::Model> = Response::from(schedules);
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+}
+
+#[rustfmt::skip]
+#[post("/api/locations/{location_id}/schedules")]
+pub async fn store(db: web::Data<DB>, id: web::Path<Id>, schedule: web::Json<NewSchedule>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let id = id.into_inner().location_id as i32;
+    let schedule = schedule.into_inner();
+    if !valid_weekday(schedule.weekday) {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Weekday must be 0 (Monday) through 6 (Sunday)")));
+    }
+    if !valid_hours(schedule.opens_at, schedule.closes_at) {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("A schedule cannot open and close at the same time")));
+    }
+    if Locations::find_by_id(id).one(db).await?.is_none() {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Error retrieving location")));
+    }
+    let new_schedule = location_schedules::ActiveModel {
+        location_id: Set(id),
+        weekday: Set(schedule.weekday),
+        opens_at: Set(schedule.opens_at),
+        closes_at: Set(schedule.closes_at),
+        allowed_levels: Set(schedule.allowed_levels),
+        allowed_units: Set(schedule.allowed_units),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    let response: Response<location_schedules::Model> = Response::from(new_schedule);
+    Ok(HttpResponse::Created().insert_header(ContentType::json()).json(response))
+}
+
+#[rustfmt::skip]
+#[get("/api/schedules/{id}")]
+pub async fn show(db: web::Data<DB>, id: web::Path<i32>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    match LocationSchedules::find_by_id(id.into_inner()).one(db).await? {
+        Some(schedule) => {
+            let response: Response<location_schedules::Model> = Response::from(schedule);
+            Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+        }
+        None => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Schedule not found"))),
+    }
+}
+
+#[rustfmt::skip]
+#[patch("/api/schedules/{id}")]
+pub async fn update(db: web::Data<DB>, id: web::Path<i32>, schedule: web::Json<UpdateSchedule>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let schedule = schedule.into_inner();
+    let Some(to_update) = LocationSchedules::find_by_id(id.into_inner()).one(db).await? else {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Schedule not found")));
+    };
+    if !valid_hours(schedule.opens_at.unwrap_or(to_update.opens_at), schedule.closes_at.unwrap_or(to_update.closes_at)) {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("A schedule cannot open and close at the same time")));
+    }
+    let mut to_update = to_update.into_active_model();
+    if let Some(weekday) = schedule.weekday {
+        if !valid_weekday(weekday) {
+            return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Weekday must be 0 (Monday) through 6 (Sunday)")));
+        }
+        to_update.weekday = Set(weekday);
+    }
+    if let Some(opens_at) = schedule.opens_at {
+        to_update.opens_at = Set(opens_at);
+    }
+    if let Some(closes_at) = schedule.closes_at {
+        to_update.closes_at = Set(closes_at);
+    }
+    if let Some(levels) = schedule.allowed_levels {
+        to_update.allowed_levels = Set(levels);
+    }
+    if let Some(units) = schedule.allowed_units {
+        to_update.allowed_units = Set(units);
+    }
+    let updated = if to_update.is_changed() { to_update.update(db).await? } else { to_update.try_into_model()? };
+    let response: Response<location_schedules::Model> = Response::from(updated);
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+}
+
+#[rustfmt::skip]
+#[delete("/api/schedules/{id}")]
+pub async fn destroy(db: web::Data<DB>, id: web::Path<i32>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    match LocationSchedules::find_by_id(id.into_inner()).one(db).await? {
+        Some(schedule) => {
+            schedule.delete(db).await?;
+            Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_success("Schedule successfully removed")))
+        }
+        None => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Schedule not found"))),
+    }
+}
@@ -0,0 +1,143 @@
+use crate::app_config::DB;
+use crate::models::{
+    badges,
+    response::Response,
+    separations::{
+        ordered_pair, NewSeparation, SeparationParams, SeparationView, UpdateSeparation,
+    },
+};
+use actix_web::{delete, get, http::header::ContentType, patch, post, web, HttpResponse};
+use chrono::Local;
+use entity::{
+    residents::{self, Entity as Residents},
+    separations::{self, Entity as Separations},
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait,
+    IntoActiveModel, ModelTrait, QueryFilter, QueryOrder, Set, SqlErr,
+};
+use std::collections::HashMap;
+
+/// The residents on either side of `seps`, by id
+async fn resident_map(
+    db: &DatabaseConnection,
+    seps: &[separations::Model],
+) -> Result<HashMap<i32, residents::Model>, DbErr> {
+    let ids = seps
+        .iter()
+        .flat_map(|sep| [sep.resident_id, sep.separated_id]);
+    Ok(Residents::find()
+        .filter(residents::Column::Id.is_in(ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|r| (r.id, r))
+        .collect())
+}
+
+async fn find_resident(
+    db: &DatabaseConnection,
+    rfid: &str,
+) -> Result<Option<residents::Model>, DbErr> {
+    Residents::find()
+        .filter(badges::matches(rfid))
+        .one(db)
+        .await
+}
+
+// index all separations, optionally only those involving ?rfid=
+#[rustfmt::skip]
+#[get("/api/separations")]
+pub async fn index(db: web::Data<DB>, params: web::Query<SeparationParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let mut query = Separations::find().order_by_asc(separations::Column::Id);
+    if let Some(rfid) = params.into_inner().rfid {
+        let Some(resident) = find_resident(db, &rfid).await? else {
+            return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::resident_not_found()));
+        };
+        query = query.filter(
+            Condition::any()
+                .add(separations::Column::ResidentId.eq(resident.id))
+                .add(separations::Column::SeparatedId.eq(resident.id)),
+        );
+    }
+    let seps = query.all(db).await?;
+    let residents = resident_map(db, &seps).await?;
+    let seps: Vec<SeparationView> = seps.into_iter().map(|sep| SeparationView::new(sep, &residents)).collect();
+    let response: Response<SeparationView> = Response::from(seps);
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+}
+
+#[rustfmt::skip]
+#[get("/api/separations/{id}")]
+pub async fn show(db: web::Data<DB>, id: web::Path<i32>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    match Separations::find_by_id(id.into_inner()).one(db).await? {
+        Some(sep) => {
+            let residents = resident_map(db, std::slice::from_ref(&sep)).await?;
+            let response: Response<SeparationView> = Response::from(SeparationView::new(sep, &residents));
+            Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+        }
+        None => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Separation not found"))),
+    }
+}
+
+#[rustfmt::skip]
+#[post("/api/separations")]
+pub async fn store(db: web::Data<DB>, sep: web::Json<NewSeparation>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let sep = sep.into_inner();
+    let (Some(resident), Some(separated)) = (find_resident(db, &sep.resident).await?, find_resident(db, &sep.separated).await?) else {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::resident_not_found()));
+    };
+    if resident.id == separated.id {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("A resident cannot be separated from themselves")));
+    }
+    let (resident_id, separated_id) = ordered_pair(resident.id, separated.id);
+    let new_sep = separations::ActiveModel {
+        resident_id: Set(resident_id),
+        separated_id: Set(separated_id),
+        reason: Set(sep.reason),
+        created_at: Set(Local::now().naive_local()),
+        ..Default::default()
+    };
+    match new_sep.insert(db).await {
+        Ok(sep) => {
+            let residents = resident_map(db, std::slice::from_ref(&sep)).await?;
+            let response: Response<SeparationView> = Response::from(SeparationView::new(sep, &residents));
+            Ok(HttpResponse::Created().insert_header(ContentType::json()).json(response))
+        }
+        Err(err) if matches!(err.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("These residents are already separated"))),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[rustfmt::skip]
+#[patch("/api/separations/{id}")]
+pub async fn update(db: web::Data<DB>, id: web::Path<i32>, sep: web::Json<UpdateSeparation>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let Some(to_update) = Separations::find_by_id(id.into_inner()).one(db).await? else {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Separation not found")));
+    };
+    let mut to_update = to_update.into_active_model();
+    if let Some(reason) = sep.into_inner().reason {
+        to_update.reason = Set(reason);
+    }
+    let updated = to_update.update(db).await?;
+    let residents = resident_map(db, std::slice::from_ref(&updated)).await?;
+    let response: Response<SeparationView> = Response::from(SeparationView::new(updated, &residents));
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+}
+
+#[rustfmt::skip]
+#[delete("/api/separations/{id}")]
+pub async fn destroy(db: web::Data<DB>, id: web::Path<i32>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    match Separations::find_by_id(id.into_inner()).one(db).await? {
+        Some(sep) => {
+            sep.delete(db).await?;
+            Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_success("Separation successfully removed")))
+        }
+        None => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Separation not found"))),
+    }
+}
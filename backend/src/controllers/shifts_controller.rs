@@ -0,0 +1,91 @@
+use crate::app_config::DB;
+use crate::models::{
+    response::Response,
+    shifts::{all_shifts, current_shift, NewShift, ShiftRun, UpdateShift},
+};
+use actix_web::{delete, get, http::header::ContentType, patch, post, web, HttpResponse};
+use chrono::Local;
+use entity::shifts::{self, Entity as Shifts};
+use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel, ModelTrait, Set};
+
+#[rustfmt::skip]
+#[get("/api/shifts")]
+pub async fn index(db: web::Data<DB>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let response: Response<shifts::Model> = Response::from(all_shifts(db).await?);
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+}
+
+// the shift in progress, with when this run of it started and ends
+#[rustfmt::skip]
+#[get("/api/shifts/current")]
+pub async fn current(db: web::Data<DB>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    match current_shift(db, Local::now().naive_local()).await? {
+        Some(run) => {
+            let response: Response<ShiftRun> = Response::from(run);
+            Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+        }
+        None => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("No shift covers the current time"))),
+    }
+}
+
+#[rustfmt::skip]
+#[post("/api/shifts")]
+pub async fn store(db: web::Data<DB>, shift: web::Json<NewShift>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let shift = shift.into_inner();
+    let new_shift = shifts::ActiveModel {
+        name: Set(shift.name),
+        starts_at: Set(shift.starts_at),
+        ends_at: Set(shift.ends_at),
+        ..Default::default()
+    };
+    match new_shift.insert(db).await {
+        Ok(shift) => {
+            let response: Response<shifts::Model> = Response::from(shift);
+            Ok(HttpResponse::Created().insert_header(ContentType::json()).json(response))
+        }
+        Err(_) => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Error adding shift: name may already exist"))),
+    }
+}
+
+#[rustfmt::skip]
+#[patch("/api/shifts/{id}")]
+pub async fn update(db: web::Data<DB>, id: web::Path<i32>, shift: web::Json<UpdateShift>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let shift = shift.into_inner();
+    let Some(to_update) = Shifts::find_by_id(id.into_inner()).one(db).await? else {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::shift_not_found()));
+    };
+    let mut to_update = to_update.into_active_model();
+    if let Some(name) = shift.name {
+        to_update.name = Set(name);
+    }
+    if let Some(starts_at) = shift.starts_at {
+        to_update.starts_at = Set(starts_at);
+    }
+    if let Some(ends_at) = shift.ends_at {
+        to_update.ends_at = Set(ends_at);
+    }
+    match to_update.update(db).await {
+        Ok(updated) => {
+            let response: Response<shifts::Model> = Response::from(updated);
+            Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+        }
+        Err(_) => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Error updating shift: name may already exist"))),
+    }
+}
+
+#[rustfmt::skip]
+#[delete("/api/shifts/{id}")]
+pub async fn destroy(db: web::Data<DB>, id: web::Path<i32>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    match Shifts::find_by_id(id.into_inner()).one(db).await? {
+        Some(shift) => {
+            shift.delete(db).await?;
+            Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_success("Shift successfully removed")))
+        }
+        None => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::shift_not_found())),
+    }
+}
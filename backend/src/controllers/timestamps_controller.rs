@@ -1,93 +1,148 @@
 use crate::{
     app_config::DB,
+    models::idempotency,
+    models::movements::{apply_scan, record_scan, Debouncer, ScanClock},
+    models::occupancy::{occupants_at, AtParams, LocationSnapshot},
     models::response::Response,
-    models::timestamps::{PostTimestamp, RangeParams, ResidentTimestamp},
-};
-use actix_web::{get, http::header::ContentType, post, web, HttpResponse, Responder};
-use chrono::{Duration, Local};
-use entity::{
-    residents::{self, Entity as Resident},
-    timestamps::{self, Entity as Timestamp},
+    models::shifts::{resolve_shift, shift_window, ShiftParams},
+    models::timestamps::{
+        timestamps_between, timestamps_within, BatchResult, PostTimestamp, RangeParams,
+    },
 };
+use actix_web::{get, http::header::ContentType, post, web, HttpRequest, HttpResponse};
+use chrono::Local;
+use entity::{locations::Entity as Location, timestamps};
 use reqwest::StatusCode;
-use sea_orm::{
-    ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, PaginatorTrait, QueryFilter, Set,
-    TryIntoModel,
-};
+use sea_orm::{DbErr, EntityTrait, PaginatorTrait, TransactionTrait};
 use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Most scans accepted in one batch upload
+pub const MAX_BATCH: usize = 1000;
+
+/// Body limit for batch uploads
+pub const BATCH_JSON_LIMIT: usize = 256 * 1024;
 
 #[derive(Debug, Deserialize)]
 pub struct FilterOpts {
     pub unique: Option<bool>,
     pub per_page: Option<u64>,
+    /// include sub-locations on location timestamps, defaults to false
+    pub rollup: Option<bool>,
 }
 
-/// GET: /api/timestamps?unique=true/false  DEFAULT: Today
+/// GET: /api/timestamps?unique=true/false&shift=day  DEFAULT: current shift
 #[rustfmt::skip]
 #[get("/api/timestamps")]
-pub async fn index_timestamps(db: web::Data<DB>, uni: web::Query<FilterOpts>) -> impl Responder {
+pub async fn index_timestamps(db: web::Data<DB>, uni: web::Query<FilterOpts>, shift: web::Query<ShiftParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
     let page = uni.per_page.unwrap_or(0);
     let db = &db.0;
-    let paginator = Timestamp::find()
-    .filter(
-        timestamps::Column::Ts.between(
-            Local::now().naive_local() - Duration::days(1),
-            Local::now().naive_local(),
-        ),
-    )
-        .paginate(db, 15);
+    let Some((start, end)) = shift_window(db, shift.shift.as_deref(), Local::now().naive_local()).await? else {
+        return Ok(HttpResponse::Ok().content_type(ContentType::json()).json(Response::<String>::shift_not_found()));
+    };
+    let paginator = timestamps_within(start, end).paginate(db, 15);
     let ts = paginator.fetch_page(page).await.unwrap_or(Vec::new());
     let response: Response<timestamps::Model> = Response::from(ts);
-    HttpResponse::Ok()
+    Ok(HttpResponse::Ok()
                 .content_type(ContentType::json())
-                .json(response)
+                .json(response))
 }
 
-/// POST: /api/timestamps/{timestamp}
+/// POST: /api/timestamps/{timestamp}  optional Idempotency-Key header
 #[rustfmt::skip]
 #[post("/api/timestamps")]
-pub async fn store_timestamp(db: web::Data<DB>, timestamp_data: web::Json<PostTimestamp>) -> Result<HttpResponse, Box<dyn std::error::Error>>{
+pub async fn store_timestamp(db: web::Data<DB>, debouncer: web::Data<Debouncer>, clock: web::Data<ScanClock>, req: HttpRequest, timestamp_data: web::Json<PostTimestamp>) -> Result<HttpResponse, Box<dyn std::error::Error>>{
     let db = &db.0;
-    let mut timestamp = timestamp_data.into_inner();
-    match Resident::find().filter(residents::Column::Rfid.eq(timestamp.rfid.clone())).one(db).await? {
-        Some(resident) => {
-             let mut resident = resident.into_active_model();
-                if timestamp.location == resident.current_location.to_owned().unwrap() {
-                    resident.current_location = Set(0);
-                    timestamp.location = 0;
-                } else {
-                    resident.current_location = Set(timestamp.location);
-                }
-
-                let updated_resident = resident.save(db).await?;
-                let new_timestamp: timestamps::ActiveModel = timestamps::ActiveModel {
-                        rfid: Set(updated_resident.id.to_owned().unwrap()),
-                        location: Set(updated_resident.current_location.to_owned().unwrap()),
-                    ..Default::default()
-                };
-                let new_ts = new_timestamp.save(db).await?;
+    let scan = timestamp_data.into_inner();
+    let key = req.headers().get(idempotency::HEADER).and_then(|value| value.to_str().ok());
+    let (status, body) = apply_scan(db, &scan, key, &debouncer, &clock).await?;
+    Ok(HttpResponse::build(StatusCode::from_u16(status)?).content_type(ContentType::json()).json(body))
+}
 
-                let response = Response::<ResidentTimestamp>::from(ResidentTimestamp {
-                    resident: updated_resident.try_into_model().unwrap(),
-                    timestamp: new_ts.try_into_model().unwrap(),
-                });
-                Ok(HttpResponse::Ok().content_type(ContentType::json()).status(StatusCode::CREATED).json(response))
-        }
-        None => {
-            let error_resp: Response<String> = Response::from_error(&String::from("Error retrieving resident: Not found in system, please add Resident."));
-            Ok(HttpResponse::Ok().content_type(ContentType::json()).json(error_resp))
+/// POST: /api/timestamps/batch  scans an offline kiosk queued up, each needs its `scanned_at`.
+/// Registered in main with a larger JSON limit than single scans get.
+#[rustfmt::skip]
+pub async fn store_batch(db: web::Data<DB>, debouncer: web::Data<Debouncer>, clock: web::Data<ScanClock>, batch: web::Json<Vec<PostTimestamp>>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let batch = batch.into_inner();
+    if batch.len() > MAX_BATCH {
+        let error_resp: Response<String> = Response::from_error(&format!("A batch can hold at most {} scans", MAX_BATCH));
+        return Ok(HttpResponse::Ok().content_type(ContentType::json()).json(error_resp));
+    }
+    if let Some(index) = batch.iter().position(|scan| scan.scanned_at.is_none()) {
+        let error_resp: Response<String> = Response::from_error(&format!("Scan {} has no scanned_at", index));
+        return Ok(HttpResponse::Ok().content_type(ContentType::json()).json(error_resp));
+    }
+    // oldest first, so each resident's scans toggle them in the order they happened
+    let mut order: Vec<usize> = (0..batch.len()).collect();
+    order.sort_by_key(|&index| batch[index].scanned_at);
+    // a kiosk's backlog keeps its scan times however long it was offline
+    let clock = clock.for_batch();
+    let mut results = Vec::with_capacity(batch.len());
+    for index in order {
+        let item = &batch[index];
+        // one transaction per scan, a refused or failed scan must not undo the others;
+        // a failed one's transaction rolls back as it's dropped
+        let recorded = async {
+            let txn = db.begin().await?;
+            let outcome = record_scan(&txn, item, &debouncer, &clock).await?;
+            txn.commit().await?;
+            Ok::<_, DbErr>(outcome)
+        }.await;
+        let outcome = match recorded {
+            Ok(outcome) => outcome,
+            Err(err) => {
+                results.push(BatchResult::failed(index, item.rfid.clone(), &err));
+                continue;
+            }
+        };
+        if let Some(seen) = outcome.seen(item) {
+            debouncer.remember(seen);
         }
+        results.push(BatchResult::new(index, item.rfid.clone(), outcome));
     }
+    results.sort_by_key(|result| result.index);
+    let response = Response::<BatchResult>::from(results);
+    Ok(HttpResponse::Ok().content_type(ContentType::json()).json(response))
 }
 
-/// GET: /api/timestamps/{start}/{end}
+/// GET: /api/timestamps/{start}/{end}?shift=night  only the shift's hours on each day
 #[get("/api/timestamps/{start_date}/{end_date}")]
 #[rustfmt::skip]
-pub async fn show_range(db: web::Data<DB>, range: web::Path<RangeParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+pub async fn show_range(db: web::Data<DB>, range: web::Path<RangeParams>, shift: web::Query<ShiftParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
     let db = &db.0;
     let range = range.into_inner();
-    let time: Vec<entity::timestamps::Model>
-    = Timestamp::find().filter(entity::timestamps::Column::Ts.between(range.start_date, range.end_date)).all(db).await?;
+    let shift = match shift.into_inner().shift {
+        Some(name) => match resolve_shift(db, &name, Local::now().naive_local()).await? {
+            Some(shift) => Some(shift),
+            None => return Ok(HttpResponse::Ok().content_type(ContentType::json()).json(Response::<String>::shift_not_found())),
+        },
+        None => None,
+    };
+    let time: Vec<entity::timestamps::Model> = timestamps_between(range.start_date, range.end_date, shift.as_ref()).all(db).await?;
     let response = Response::<timestamps::Model>::from(time);
     Ok(HttpResponse::Ok().content_type(ContentType::json()).json(response))
 }
+
+/// GET: /api/snapshot?at=2023-11-21T14:32:00  Every occupied location at that moment
+#[get("/api/snapshot")]
+#[rustfmt::skip]
+pub async fn snapshot(db: web::Data<DB>, at: web::Query<AtParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let occupants = occupants_at(db, at.into_inner().at, None).await?;
+    let names: HashMap<i32, String> = Location::find().all(db).await?.into_iter().map(|loc| (loc.id, loc.name)).collect();
+    // occupants come back ordered by location, so group consecutive runs
+    let mut snapshot: Vec<LocationSnapshot> = Vec::new();
+    for occupant in occupants {
+        match snapshot.last_mut() {
+            Some(last) if last.location == occupant.location => last.occupants.push(occupant),
+            _ => snapshot.push(LocationSnapshot {
+                location: occupant.location,
+                name: names.get(&occupant.location).cloned().unwrap_or_default(),
+                occupants: vec![occupant],
+            }),
+        }
+    }
+    let response = Response::<LocationSnapshot>::from(snapshot);
+    Ok(HttpResponse::Ok().content_type(ContentType::json()).json(response))
+}
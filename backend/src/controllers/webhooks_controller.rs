@@ -0,0 +1,137 @@
+use crate::app_config::DB;
+use crate::models::{
+    response::Response,
+    webhooks::{retry, validate, DeliveryParams, NewWebhook, UpdateWebhook},
+};
+use actix_web::{delete, get, http::header::ContentType, patch, post, web, HttpResponse};
+use chrono::Local;
+use entity::{
+    sea_orm_active_enums::DeliveryStatus,
+    webhook_deliveries::{self, Entity as WebhookDeliveries},
+    webhooks::{self, Entity as Webhooks},
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, ModelTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, Set,
+};
+
+#[rustfmt::skip]
+#[get("/api/webhooks")]
+pub async fn index(db: web::Data<DB>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let hooks = Webhooks::find().order_by_asc(webhooks::Column::Id).all(db).await?;
+    let response: Response<webhooks::Model> = Response::from(hooks);
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+}
+
+#[rustfmt::skip]
+#[get("/api/webhooks/{id}")]
+pub async fn show(db: web::Data<DB>, id: web::Path<i32>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    match Webhooks::find_by_id(id.into_inner()).one(db).await? {
+        Some(hook) => {
+            let response: Response<webhooks::Model> = Response::from(hook);
+            Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+        }
+        None => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::webhook_not_found())),
+    }
+}
+
+// the secret is write-only, it never comes back in a response
+#[rustfmt::skip]
+#[post("/api/webhooks")]
+pub async fn store(db: web::Data<DB>, hook: web::Json<NewWebhook>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let hook = hook.into_inner();
+    if let Err(message) = validate(Some(&hook.url), Some(&hook.events)) {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error(&message)));
+    }
+    if hook.secret.is_empty() {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("A webhook needs a secret to sign its deliveries")));
+    }
+    let new_hook = webhooks::ActiveModel {
+        url: Set(hook.url),
+        events: Set(hook.events),
+        secret: Set(hook.secret),
+        active: Set(true),
+        created_at: Set(Local::now().naive_local()),
+        ..Default::default()
+    };
+    let response: Response<webhooks::Model> = Response::from(new_hook.insert(db).await?);
+    Ok(HttpResponse::Created().insert_header(ContentType::json()).json(response))
+}
+
+#[rustfmt::skip]
+#[patch("/api/webhooks/{id}")]
+pub async fn update(db: web::Data<DB>, id: web::Path<i32>, hook: web::Json<UpdateWebhook>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let hook = hook.into_inner();
+    if let Err(message) = validate(hook.url.as_deref(), hook.events.as_deref()) {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error(&message)));
+    }
+    let Some(to_update) = Webhooks::find_by_id(id.into_inner()).one(db).await? else {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::webhook_not_found()));
+    };
+    let mut to_update = to_update.into_active_model();
+    if let Some(url) = hook.url {
+        to_update.url = Set(url);
+    }
+    if let Some(events) = hook.events {
+        to_update.events = Set(events);
+    }
+    if let Some(secret) = hook.secret.filter(|secret| !secret.is_empty()) {
+        to_update.secret = Set(secret);
+    }
+    if let Some(active) = hook.active {
+        to_update.active = Set(active);
+    }
+    let response: Response<webhooks::Model> = Response::from(to_update.update(db).await?);
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+}
+
+// deleting a webhook drops its delivery log along with it
+#[rustfmt::skip]
+#[delete("/api/webhooks/{id}")]
+pub async fn destroy(db: web::Data<DB>, id: web::Path<i32>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    match Webhooks::find_by_id(id.into_inner()).one(db).await? {
+        Some(hook) => {
+            hook.delete(db).await?;
+            Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_success("Webhook successfully removed")))
+        }
+        None => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::webhook_not_found())),
+    }
+}
+
+/// GET: /api/webhooks/{id}/deliveries?status=failed&page=0  Newest first
+#[rustfmt::skip]
+#[get("/api/webhooks/{id}/deliveries")]
+pub async fn deliveries(db: web::Data<DB>, id: web::Path<i32>, params: web::Query<DeliveryParams>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    let params = params.into_inner();
+    let Some(hook) = Webhooks::find_by_id(id.into_inner()).one(db).await? else {
+        return Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::webhook_not_found()));
+    };
+    let mut query = hook.find_related(WebhookDeliveries).order_by_desc(webhook_deliveries::Column::Id);
+    if let Some(status) = params.status {
+        query = query.filter(webhook_deliveries::Column::Status.eq(status));
+    }
+    let deliveries = query.paginate(db, 50).fetch_page(params.page.unwrap_or(0)).await?;
+    let response: Response<webhook_deliveries::Model> = Response::from(deliveries);
+    Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+}
+
+// send a failed delivery again, starting its retries over
+#[rustfmt::skip]
+#[post("/api/webhooks/deliveries/{id}/retry")]
+pub async fn retry_delivery(db: web::Data<DB>, id: web::Path<i32>) -> Result<HttpResponse, Box<dyn std::error::Error>> {
+    let db = &db.0;
+    match WebhookDeliveries::find_by_id(id.into_inner()).one(db).await? {
+        Some(delivery) if delivery.status == DeliveryStatus::Failed => {
+            let response: Response<webhook_deliveries::Model> = Response::from(retry(db, delivery).await?);
+            Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(response))
+        }
+        Some(_) => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Only failed deliveries can be retried"))),
+        None => Ok(HttpResponse::Ok().insert_header(ContentType::json()).json(Response::<String>::from_error("Delivery not found"))),
+    }
+}
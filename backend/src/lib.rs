@@ -3,3 +3,9 @@ pub mod models;
 pub mod controllers;
 
 pub mod app_config;
+
+pub mod reports;
+
+pub mod mqtt;
+
+pub mod photos;
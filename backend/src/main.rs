@@ -1,14 +1,29 @@
 use actix_cors::Cors;
 use actix_web::{
     middleware,
-    web::{Data, JsonConfig},
+    web::{self, Data, JsonConfig, PayloadConfig},
     App, HttpServer,
 };
 use scan_mvcf::{
     app_config::DB,
-    controllers::{locations_controller, residents_controller, timestamps_controller},
+    controllers::{
+        admin_controller, alerts_controller, appointments_controller, locations_controller,
+        notes_controller, photos_controller, reports_controller, residents_controller,
+        rules_controller, schedules_controller, separations_controller, shifts_controller,
+        timestamps_controller, webhooks_controller,
+    },
+    models::{
+        appointments, idempotency,
+        movements::{Debouncer, ScanClock},
+        rules, webhooks,
+    },
+    mqtt::{self, MqttConfig},
+    photos::{
+        self,
+        storage::{LocalDisk, PhotoStorage},
+    },
 };
-use std::io;
+use std::{io, sync::Arc};
 
 #[actix_web::main]
 async fn main() -> io::Result<()> {
@@ -19,6 +34,7 @@ async fn main() -> io::Result<()> {
     if args.len() > 1 {
         let arg = &args[1];
         if arg == "--test" {
+            #[allow(clippy::zombie_processes)]
             let _ = std::process::Command::new("python")
                 .args(["test.py"])
                 .spawn()
@@ -30,6 +46,25 @@ async fn main() -> io::Result<()> {
     let json_config = JsonConfig::default().limit(4096);
     if let Ok(db) = DB::get().await {
         log::info!("Connected to database");
+        let debouncer = Data::new(Debouncer::default());
+        let clock = Data::new(ScanClock::from_env());
+        let storage: Arc<dyn PhotoStorage> = Arc::new(LocalDisk::from_env()?);
+        let storage = Data::from(storage);
+        actix_web::rt::spawn(webhooks::run(db.0.clone(), webhooks::poll_interval()));
+        actix_web::rt::spawn(rules::run(db.0.clone(), rules::sweep_interval()));
+        actix_web::rt::spawn(appointments::run(
+            db.0.clone(),
+            appointments::settle_interval(),
+        ));
+        actix_web::rt::spawn(idempotency::run(db.0.clone()));
+        if let Some(config) = MqttConfig::from_env() {
+            actix_web::rt::spawn(mqtt::run(
+                db.0.clone(),
+                config,
+                debouncer.clone().into_inner(),
+                clock.clone().into_inner(),
+            ));
+        }
 
         HttpServer::new(move || {
             let cors = Cors::permissive()
@@ -42,22 +77,92 @@ async fn main() -> io::Result<()> {
             App::new()
                 .app_data(Data::new(db.clone()))
                 .app_data(json_config.clone())
+                .app_data(debouncer.clone())
+                .app_data(clock.clone())
+                .app_data(storage.clone())
                 .service(locations_controller::index)
                 .service(locations_controller::show)
                 .service(locations_controller::show_location_timestamps)
                 .service(locations_controller::show_location_timestamps_range)
                 .service(locations_controller::show_location_residents)
+                .service(locations_controller::show_location_occupants)
                 .service(locations_controller::store)
+                .service(locations_controller::update)
                 .service(residents_controller::index)
+                .service(residents_controller::away)
+                .service(residents_controller::search)
+                .service(residents_controller::show_by_doc)
+                .service(residents_controller::update_by_doc)
+                .service(residents_controller::show_by_id)
+                .service(residents_controller::update_by_id)
                 .service(residents_controller::show)
                 .service(residents_controller::show_resident_timestamps)
                 .service(residents_controller::show_resident_timestamps_range)
+                .service(residents_controller::show_resident_contacts)
                 .service(residents_controller::store)
                 .service(residents_controller::destroy)
                 .service(residents_controller::update)
+                .service(
+                    web::resource("/api/residents/{rfid}/photo")
+                        .app_data(PayloadConfig::new(photos::MAX_BYTES))
+                        .route(web::put().to(photos_controller::upload))
+                        .route(web::delete().to(photos_controller::destroy)),
+                )
+                .service(photos_controller::show)
+                .service(notes_controller::index)
+                .service(notes_controller::store)
+                .service(notes_controller::show)
+                .service(notes_controller::update)
+                .service(notes_controller::destroy)
                 .service(timestamps_controller::index_timestamps)
                 .service(timestamps_controller::show_range)
                 .service(timestamps_controller::store_timestamp)
+                .service(
+                    web::resource("/api/timestamps/batch")
+                        .app_data(
+                            JsonConfig::default().limit(timestamps_controller::BATCH_JSON_LIMIT),
+                        )
+                        .route(web::post().to(timestamps_controller::store_batch)),
+                )
+                .service(timestamps_controller::snapshot)
+                .service(separations_controller::index)
+                .service(separations_controller::show)
+                .service(separations_controller::store)
+                .service(separations_controller::update)
+                .service(separations_controller::destroy)
+                .service(alerts_controller::index)
+                .service(alerts_controller::acknowledge)
+                .service(alerts_controller::resolve)
+                .service(rules_controller::index)
+                .service(rules_controller::show)
+                .service(rules_controller::store)
+                .service(rules_controller::update)
+                .service(rules_controller::destroy)
+                .service(schedules_controller::index)
+                .service(schedules_controller::store)
+                .service(schedules_controller::show)
+                .service(schedules_controller::update)
+                .service(schedules_controller::destroy)
+                .service(appointments_controller::index)
+                .service(appointments_controller::show)
+                .service(appointments_controller::store)
+                .service(appointments_controller::update)
+                .service(appointments_controller::destroy)
+                .service(reports_controller::movement_log)
+                .service(shifts_controller::index)
+                .service(shifts_controller::current)
+                .service(shifts_controller::store)
+                .service(shifts_controller::update)
+                .service(shifts_controller::destroy)
+                .service(webhooks_controller::index)
+                .service(webhooks_controller::store)
+                .service(webhooks_controller::retry_delivery)
+                .service(webhooks_controller::show)
+                .service(webhooks_controller::update)
+                .service(webhooks_controller::destroy)
+                .service(webhooks_controller::deliveries)
+                .service(admin_controller::consistency)
+                .service(admin_controller::repair_consistency)
                 .wrap(middleware::Logger::default())
                 .wrap(cors)
         })
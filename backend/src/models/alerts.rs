@@ -0,0 +1,126 @@
+use super::webhooks;
+use entity::{alerts, rules, sea_orm_active_enums::RuleKind};
+use sea_orm::{ActiveEnum, ActiveModelTrait, ConnectionTrait, DbErr, Set};
+use serde::Deserialize;
+
+pub const SEPARATION: &str = "separation";
+pub const OVER_CAPACITY: &str = "over_capacity";
+pub const CLOSED_LOCATION: &str = "closed_location";
+pub const CLOCK_SKEW: &str = "clock_skew";
+pub const OVERDUE_PASS: &str = "overdue_pass";
+
+#[derive(Debug, Deserialize)]
+pub struct AlertParams {
+    pub kind: Option<String>,
+    pub status: Option<AlertStatus>,
+    pub rule: Option<i32>,
+    pub page: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertStatus {
+    /// not resolved yet, acknowledged or not
+    Open,
+    /// acknowledged but not resolved
+    Acknowledged,
+    Resolved,
+}
+
+/// Body of the acknowledge and resolve endpoints, `by` is the staff member
+#[derive(Debug, Deserialize)]
+pub struct AlertAction {
+    pub by: String,
+}
+
+/// A scan-time check that failed. `refuse` says whether the movement must be
+/// rejected rather than only flagged. Nothing is recorded until the caller has
+/// run every check and knows which breaches stand, see [`Breach::raise`].
+pub struct Breach {
+    pub refuse: bool,
+    pub kind: &'static str,
+    pub resident_id: i32,
+    pub location: i32,
+    pub message: String,
+}
+
+impl Breach {
+    pub async fn raise<C: ConnectionTrait>(self, db: &C) -> Result<alerts::Model, DbErr> {
+        raise(
+            db,
+            self.kind,
+            Some(self.resident_id),
+            Some(self.location),
+            self.message,
+        )
+        .await
+    }
+}
+
+/// Record an alert so it shows up on GET /api/alerts
+pub async fn raise<C: ConnectionTrait>(
+    db: &C,
+    kind: &str,
+    resident_id: Option<i32>,
+    location: Option<i32>,
+    message: String,
+) -> Result<alerts::Model, DbErr> {
+    insert(db, kind, resident_id, location, message, None).await
+}
+
+/// Record an alert raised by a rule, kept open until the rule clears or staff resolve it
+pub async fn raise_for_rule<C: ConnectionTrait>(
+    db: &C,
+    rule: &rules::Model,
+    resident_id: Option<i32>,
+    location: Option<i32>,
+    message: String,
+) -> Result<alerts::Model, DbErr> {
+    // time spent in AWAY is what staff and webhooks know as an overdue pass
+    let kind = match rule.kind {
+        RuleKind::AwayTooLong => OVERDUE_PASS.to_string(),
+        kind => kind.to_value(),
+    };
+    let message = format!("{}: {}", rule.name, message);
+    insert(db, &kind, resident_id, location, message, Some(rule.id)).await
+}
+
+async fn insert<C: ConnectionTrait>(
+    db: &C,
+    kind: &str,
+    resident_id: Option<i32>,
+    location: Option<i32>,
+    message: String,
+    rule_id: Option<i32>,
+) -> Result<alerts::Model, DbErr> {
+    log::warn!("ALERT [{}]: {}", kind, message);
+    let alert = alerts::ActiveModel {
+        kind: Set(kind.to_string()),
+        resident_id: Set(resident_id),
+        location: Set(location),
+        message: Set(message),
+        created_at: Set(chrono::Local::now().naive_local()),
+        rule_id: Set(rule_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    match kind {
+        OVER_CAPACITY => webhooks::enqueue(db, webhooks::OVER_CAPACITY, &alert).await?,
+        OVERDUE_PASS => webhooks::enqueue(db, webhooks::OVERDUE_PASS, &alert).await?,
+        _ => {}
+    }
+    Ok(alert)
+}
+
+/// Mark an alert resolved, `by` is who or what cleared it
+pub async fn resolve<C: ConnectionTrait>(
+    db: &C,
+    alert: alerts::Model,
+    by: &str,
+) -> Result<alerts::Model, DbErr> {
+    let mut alert: alerts::ActiveModel = alert.into();
+    alert.resolved_at = Set(Some(chrono::Local::now().naive_local()));
+    alert.resolved_by = Set(Some(by.to_string()));
+    alert.update(db).await
+}
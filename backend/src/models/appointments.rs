@@ -0,0 +1,195 @@
+use super::occupancy::{deserialize_datetime, deserialize_optional_datetime};
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime};
+use entity::{
+    appointments::{self, Entity as Appointments},
+    locations::Entity as Locations,
+    residents::{self, Entity as Residents},
+    sea_orm_active_enums::{AppointmentKind, AppointmentStatus},
+    timestamps::{self, Entity as Timestamps},
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    IntoActiveModel, QueryFilter, QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A scan this many minutes before the start still counts as arriving for it
+pub const EARLY_MINUTES: i64 = 30;
+/// Arriving more than this many minutes after the start is late
+pub const LATE_AFTER_MINUTES: i64 = 5;
+
+#[derive(Debug, Deserialize)]
+pub struct NewAppointment {
+    pub rfid: String,
+    pub location: i32,
+    #[serde(default)]
+    pub kind: AppointmentKind,
+    #[serde(deserialize_with = "deserialize_datetime")]
+    pub starts_at: NaiveDateTime,
+    #[serde(deserialize_with = "deserialize_datetime")]
+    pub ends_at: NaiveDateTime,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateAppointment {
+    pub location: Option<i32>,
+    pub kind: Option<AppointmentKind>,
+    #[serde(default, deserialize_with = "deserialize_optional_datetime")]
+    pub starts_at: Option<NaiveDateTime>,
+    #[serde(default, deserialize_with = "deserialize_optional_datetime")]
+    pub ends_at: Option<NaiveDateTime>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub note: Option<Option<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppointmentParams {
+    /// appointments starting on this day
+    pub date: Option<NaiveDate>,
+    /// this location and its sub-locations
+    pub location: Option<i32>,
+    pub rfid: Option<String>,
+    pub status: Option<AppointmentStatus>,
+}
+
+/// An appointment with its resident and location spelled out
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppointmentView {
+    pub id: i32,
+    pub rfid: String,
+    pub name: String,
+    pub doc: String,
+    pub location: i32,
+    pub location_name: String,
+    pub kind: AppointmentKind,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+    pub status: AppointmentStatus,
+    pub arrived_at: Option<NaiveDateTime>,
+    pub note: Option<String>,
+}
+
+pub async fn appointment_views<C: ConnectionTrait>(
+    db: &C,
+    appts: Vec<appointments::Model>,
+) -> Result<Vec<AppointmentView>, DbErr> {
+    let residents: HashMap<i32, residents::Model> = Residents::find()
+        .filter(residents::Column::Id.is_in(appts.iter().map(|a| a.resident_id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|r| (r.id, r))
+        .collect();
+    let names: HashMap<i32, String> = Locations::find()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|loc| (loc.id, loc.name))
+        .collect();
+    Ok(appts
+        .into_iter()
+        .map(|appt| {
+            let resident = residents.get(&appt.resident_id);
+            AppointmentView {
+                id: appt.id,
+                rfid: resident.map(|r| r.rfid.clone()).unwrap_or_default(),
+                name: resident.map(|r| r.name.clone()).unwrap_or_default(),
+                doc: resident.map(|r| r.doc.clone()).unwrap_or_default(),
+                location: appt.location_id,
+                location_name: names.get(&appt.location_id).cloned().unwrap_or_default(),
+                kind: appt.kind,
+                starts_at: appt.starts_at,
+                ends_at: appt.ends_at,
+                status: appt.status,
+                arrived_at: appt.arrived_at,
+                note: appt.note,
+            }
+        })
+        .collect())
+}
+
+fn status_for(appt: &appointments::Model, arrived: NaiveDateTime) -> AppointmentStatus {
+    if arrived <= appt.starts_at + Duration::minutes(LATE_AFTER_MINUTES) {
+        AppointmentStatus::Attended
+    } else {
+        AppointmentStatus::Late
+    }
+}
+
+/// Called after a resident scans in: settles the scheduled appointment at
+/// their new location whose window covers the scan time.
+pub async fn mark_arrival<C: ConnectionTrait>(
+    db: &C,
+    resident: &residents::Model,
+    at: NaiveDateTime,
+) -> Result<Option<appointments::Model>, DbErr> {
+    if resident.current_location == 0 {
+        return Ok(None);
+    }
+    let Some(appt) = Appointments::find()
+        .filter(appointments::Column::ResidentId.eq(resident.id))
+        .filter(appointments::Column::LocationId.eq(resident.current_location))
+        .filter(appointments::Column::Status.eq(AppointmentStatus::Scheduled))
+        .filter(appointments::Column::StartsAt.lte(at + Duration::minutes(EARLY_MINUTES)))
+        .filter(appointments::Column::EndsAt.gte(at))
+        .order_by_asc(appointments::Column::StartsAt)
+        .one(db)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let status = status_for(&appt, at);
+    let mut appt = appt.into_active_model();
+    appt.status = Set(status);
+    appt.arrived_at = Set(Some(at));
+    Ok(Some(appt.update(db).await?))
+}
+
+/// Settle every appointment still scheduled after its window closed. The
+/// timestamps table is checked for an arrival first, so appointments entered
+/// after the fact are matched too; anything without one is missed.
+pub async fn settle_past<C: ConnectionTrait>(db: &C, now: NaiveDateTime) -> Result<(), DbErr> {
+    let due = Appointments::find()
+        .filter(appointments::Column::Status.eq(AppointmentStatus::Scheduled))
+        .filter(appointments::Column::EndsAt.lt(now))
+        .all(db)
+        .await?;
+    for appt in due {
+        let arrival = Timestamps::find()
+            .filter(timestamps::Column::Rfid.eq(appt.resident_id))
+            .filter(timestamps::Column::Location.eq(appt.location_id))
+            .filter(timestamps::Column::Ts.between(
+                appt.starts_at - Duration::minutes(EARLY_MINUTES),
+                appt.ends_at,
+            ))
+            .order_by_asc(timestamps::Column::Ts)
+            .one(db)
+            .await?;
+        let status = match &arrival {
+            Some(ts) => status_for(&appt, ts.ts),
+            None => AppointmentStatus::Missed,
+        };
+        let mut appt = appt.into_active_model();
+        appt.status = Set(status);
+        appt.arrived_at = Set(arrival.map(|ts| ts.ts));
+        appt.update(db).await?;
+    }
+    Ok(())
+}
+
+/// Periodic task settling appointments once their window has closed
+pub async fn run(db: DatabaseConnection, every: std::time::Duration) {
+    loop {
+        actix_web::rt::time::sleep(every).await;
+        if let Err(err) = settle_past(&db, Local::now().naive_local()).await {
+            log::error!("settling appointments: {}", err);
+        }
+    }
+}
+
+/// APPOINTMENTS_SETTLE_SECONDS, a minute when unset
+pub fn settle_interval() -> std::time::Duration {
+    crate::app_config::env_seconds("APPOINTMENTS_SETTLE_SECONDS", 60)
+}
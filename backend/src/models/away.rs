@@ -0,0 +1,79 @@
+use chrono::{Duration, NaiveDateTime};
+use entity::residents;
+use sea_orm::{ConnectionTrait, DbBackend, DbErr, FromQueryResult, Statement};
+use serde::{Deserialize, Serialize};
+
+// Residents in AWAY with their latest timestamp (the scan out) and the last one
+// somewhere other than AWAY, both served by idx_timestamps_rfid_ts
+const AWAY_RESIDENTS: &str = r#"
+SELECT r.*, t.ts AS since, t.scanner AS scanned_out_by,
+       seen.location AS last_location, seen.name AS last_location_name
+FROM residents r
+LEFT JOIN LATERAL (
+    SELECT ts, scanner FROM timestamps
+    WHERE timestamps.rfid = r.id
+    ORDER BY timestamps.ts DESC, timestamps.id DESC
+    LIMIT 1
+) t ON true
+LEFT JOIN LATERAL (
+    SELECT timestamps.location, locations.name FROM timestamps
+    LEFT JOIN locations ON locations.id = timestamps.location
+    WHERE timestamps.rfid = r.id AND timestamps.location <> 0
+    ORDER BY timestamps.ts DESC, timestamps.id DESC
+    LIMIT 1
+) seen ON true
+WHERE r.current_location = 0
+  AND ($1::int IS NULL OR r.unit = $1)
+  AND ($2::timestamp IS NULL OR t.ts <= $2)
+ORDER BY t.ts, r.name"#;
+
+#[derive(Debug, Deserialize)]
+pub struct AwayParams {
+    /// only residents away at least this long
+    pub min_minutes: Option<i64>,
+    pub unit: Option<i32>,
+}
+
+/// A resident in AWAY (location 0) and how they got there
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AwayResident {
+    pub resident: residents::Model,
+    /// when they were scanned out, `None` if they have no movements on record
+    pub since: Option<NaiveDateTime>,
+    pub minutes: Option<i64>,
+    /// where they were before going AWAY
+    pub last_location: Option<i32>,
+    pub last_location_name: Option<String>,
+    /// the scanner that took them out, when it identified itself
+    pub scanned_out_by: Option<String>,
+}
+
+/// Everyone in AWAY, longest gone first
+pub async fn away_residents<C: ConnectionTrait>(
+    db: &C,
+    params: &AwayParams,
+    now: NaiveDateTime,
+) -> Result<Vec<AwayResident>, DbErr> {
+    // out at or before this has been away at least `min_minutes`
+    let cutoff = params.min_minutes.map(|min| now - Duration::minutes(min));
+    let stmt = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        AWAY_RESIDENTS,
+        [params.unit.into(), cutoff.into()],
+    );
+    db.query_all(stmt)
+        .await?
+        .iter()
+        .map(|row| {
+            let since: Option<NaiveDateTime> = row.try_get("", "since")?;
+            Ok(AwayResident {
+                resident: residents::Model::from_query_result(row, "")?,
+                since,
+                minutes: since.map(|since| (now - since).num_minutes()),
+                last_location: row.try_get("", "last_location")?,
+                last_location_name: row.try_get("", "last_location_name")?,
+                scanned_out_by: row.try_get("", "scanned_out_by")?,
+            })
+        })
+        .collect()
+}
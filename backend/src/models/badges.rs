@@ -0,0 +1,15 @@
+use entity::residents;
+use sea_orm::{sea_query::SimpleExpr, ColumnTrait};
+
+pub use mvscan_badges::badges::{canonical, BadgeError};
+
+/// The id to look a badge up by. Anything that doesn't decode is used as is,
+/// it just won't match anyone.
+pub fn lookup_key(raw: &str) -> String {
+    canonical(raw).unwrap_or_else(|_| raw.trim().to_owned())
+}
+
+/// Filter residents by the badge `raw` decodes to
+pub fn matches(raw: &str) -> SimpleExpr {
+    residents::Column::Rfid.eq(lookup_key(raw))
+}
@@ -0,0 +1,143 @@
+use super::badges::lookup_key;
+use chrono::{Local, NaiveDateTime};
+use entity::{location_corrections, residents, timestamps};
+use sea_orm::{
+    ActiveModelTrait, ConnectionTrait, DatabaseConnection, DbBackend, DbErr, FromQueryResult, Set,
+    Statement, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+// Each resident's latest timestamp is where replaying their history leaves them
+const DISCREPANCIES: &str = r#"
+SELECT r.id AS resident_id, r.rfid, r.name, r.doc, r.current_location AS stored_location,
+       t.location AS replayed_location, t.ts AS last_scan
+FROM residents r
+CROSS JOIN LATERAL (
+    SELECT location, ts FROM timestamps
+    WHERE timestamps.rfid = r.id
+    ORDER BY timestamps.ts DESC, timestamps.id DESC
+    LIMIT 1
+) t
+WHERE r.current_location <> t.location"#;
+
+/// Which side of a discrepancy a repair keeps
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Trust {
+    /// move the resident to where their timestamps put them
+    #[default]
+    History,
+    /// keep the stored location and add a timestamp for it
+    Resident,
+}
+
+impl Trust {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Trust::History => "history",
+            Trust::Resident => "resident",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RepairParams {
+    pub trust: Option<Trust>,
+    /// repair only this resident
+    pub rfid: Option<String>,
+}
+
+/// A resident whose `current_location` disagrees with their latest timestamp
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromQueryResult)]
+pub struct Discrepancy {
+    pub resident_id: i32,
+    pub rfid: String,
+    pub name: String,
+    pub doc: String,
+    pub stored_location: i32,
+    pub replayed_location: i32,
+    pub last_scan: NaiveDateTime,
+}
+
+async fn discrepancies_with<C: ConnectionTrait>(
+    db: &C,
+    rfid: Option<&str>,
+    lock: bool,
+) -> Result<Vec<Discrepancy>, DbErr> {
+    let mut sql = DISCREPANCIES.to_string();
+    let mut values = Vec::new();
+    if let Some(rfid) = rfid {
+        sql.push_str(" AND r.rfid = $1");
+        values.push(lookup_key(rfid).into());
+    }
+    sql.push_str(" ORDER BY r.name");
+    if lock {
+        sql.push_str(" FOR UPDATE OF r");
+    }
+    Discrepancy::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        sql,
+        values,
+    ))
+    .all(db)
+    .await
+}
+
+pub async fn find_discrepancies<C: ConnectionTrait>(
+    db: &C,
+    rfid: Option<&str>,
+) -> Result<Vec<Discrepancy>, DbErr> {
+    discrepancies_with(db, rfid, false).await
+}
+
+/// Repair every discrepancy (or just `rfid`'s) in one transaction, with the
+/// residents locked, and log each correction. Returns what was repaired.
+pub async fn repair(
+    db: &DatabaseConnection,
+    trust: Trust,
+    rfid: Option<&str>,
+) -> Result<Vec<Discrepancy>, DbErr> {
+    let txn = db.begin().await?;
+    let found = discrepancies_with(&txn, rfid, true).await?;
+    let now = Local::now().naive_local();
+    for discrepancy in &found {
+        match trust {
+            Trust::History => {
+                residents::ActiveModel {
+                    id: Set(discrepancy.resident_id),
+                    current_location: Set(discrepancy.replayed_location),
+                    ..Default::default()
+                }
+                .update(&txn)
+                .await?;
+            }
+            Trust::Resident => {
+                timestamps::ActiveModel {
+                    rfid: Set(discrepancy.resident_id),
+                    location: Set(discrepancy.stored_location),
+                    ts: Set(now),
+                    // set here like every other insert, the column default
+                    // would be in the database's timezone
+                    received_at: Set(now),
+                    scanner: Set(None),
+                    scanned_at: Set(None),
+                    ..Default::default()
+                }
+                .insert(&txn)
+                .await?;
+            }
+        }
+        location_corrections::ActiveModel {
+            resident_id: Set(discrepancy.resident_id),
+            stored_location: Set(discrepancy.stored_location),
+            replayed_location: Set(discrepancy.replayed_location),
+            source: Set(trust.as_str().to_string()),
+            created_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+    }
+    txn.commit().await?;
+    Ok(found)
+}
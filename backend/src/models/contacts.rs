@@ -0,0 +1,210 @@
+use super::occupancy::{deserialize_datetime, occupants_at};
+use chrono::{Duration, Local, NaiveDateTime};
+use entity::{
+    residents::{self, Entity as Residents},
+    timestamps::{self, Entity as Timestamps},
+};
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Deserialize)]
+pub struct ContactParams {
+    #[serde(deserialize_with = "deserialize_datetime")]
+    pub from: NaiveDateTime,
+    #[serde(deserialize_with = "deserialize_datetime")]
+    pub to: NaiveDateTime,
+    /// ignore overlaps shorter than this many minutes
+    pub min_overlap: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Overlap {
+    pub location: i32,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub seconds: i64,
+}
+
+/// Another resident who shared a location with the subject during the window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contact {
+    pub rfid: String,
+    pub name: String,
+    pub doc: String,
+    pub total_seconds: i64,
+    pub overlaps: Vec<Overlap>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Interval {
+    location: i32,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+}
+
+/// Turn each resident's position at `from` plus their timestamps after it into
+/// presence intervals ending no later than `to`. AWAY (0) is not presence.
+fn replay(
+    mut open: HashMap<i32, (i32, NaiveDateTime)>,
+    moves: Vec<timestamps::Model>,
+    to: NaiveDateTime,
+) -> HashMap<i32, Vec<Interval>> {
+    let mut intervals: HashMap<i32, Vec<Interval>> = HashMap::new();
+    let mut close = |id: i32, (location, start): (i32, NaiveDateTime), end: NaiveDateTime| {
+        if location != 0 && end > start {
+            intervals.entry(id).or_default().push(Interval {
+                location,
+                start,
+                end,
+            });
+        }
+    };
+    for ts in moves {
+        if let Some(prev) = open.insert(ts.rfid, (ts.location, ts.ts)) {
+            close(ts.rfid, prev, ts.ts);
+        }
+    }
+    let end = to.min(Local::now().naive_local());
+    for (id, prev) in open {
+        close(id, prev, end);
+    }
+    intervals
+}
+
+/// Timestamps in (from, to] for the given residents, oldest first
+async fn moves_between(
+    db: &DatabaseConnection,
+    ids: Vec<i32>,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<Vec<timestamps::Model>, DbErr> {
+    Timestamps::find()
+        .filter(timestamps::Column::Rfid.is_in(ids))
+        .filter(timestamps::Column::Ts.gt(from))
+        .filter(timestamps::Column::Ts.lte(to))
+        .order_by_asc(timestamps::Column::Ts)
+        .order_by_asc(timestamps::Column::Id)
+        .all(db)
+        .await
+}
+
+/// Where the subject was during the window
+async fn subject_intervals(
+    db: &DatabaseConnection,
+    resident: &residents::Model,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<Vec<Interval>, DbErr> {
+    let open: HashMap<i32, (i32, NaiveDateTime)> = Timestamps::find()
+        .filter(timestamps::Column::Rfid.eq(resident.id))
+        .filter(timestamps::Column::Ts.lte(from))
+        .order_by_desc(timestamps::Column::Ts)
+        .order_by_desc(timestamps::Column::Id)
+        .one(db)
+        .await?
+        .map(|ts| (resident.id, (ts.location, from)))
+        .into_iter()
+        .collect();
+    let moves = moves_between(db, vec![resident.id], from, to).await?;
+    Ok(replay(open, moves, to)
+        .remove(&resident.id)
+        .unwrap_or_default())
+}
+
+/// Everyone else who was at one of `locations` at some point in the window,
+/// and where they were throughout it
+async fn others_at(
+    db: &DatabaseConnection,
+    subject: i32,
+    locations: &[i32],
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<(Vec<residents::Model>, HashMap<i32, Vec<Interval>>), DbErr> {
+    let there_at_start = occupants_at(db, from, Some(locations)).await?;
+    let arrived: Vec<i32> = Timestamps::find()
+        .select_only()
+        .column(timestamps::Column::Rfid)
+        .distinct()
+        .filter(timestamps::Column::Location.is_in(locations.iter().copied()))
+        .filter(timestamps::Column::Ts.gt(from))
+        .filter(timestamps::Column::Ts.lte(to))
+        .into_tuple()
+        .all(db)
+        .await?;
+    let residents: Vec<residents::Model> = Residents::find()
+        .filter(
+            Condition::any()
+                .add(residents::Column::Id.is_in(arrived))
+                .add(
+                    residents::Column::Rfid
+                        .is_in(there_at_start.iter().map(|occ| occ.rfid.clone())),
+                ),
+        )
+        .filter(residents::Column::Id.ne(subject))
+        .all(db)
+        .await?;
+    let ids: HashMap<&str, i32> = residents.iter().map(|r| (r.rfid.as_str(), r.id)).collect();
+    let open = there_at_start
+        .iter()
+        .filter_map(|occ| {
+            ids.get(occ.rfid.as_str())
+                .map(|id| (*id, (occ.location, from)))
+        })
+        .collect();
+    let moves = moves_between(db, ids.into_values().collect(), from, to).await?;
+    Ok((residents, replay(open, moves, to)))
+}
+
+/// Every resident who overlapped `resident` at a location between `from` and `to`,
+/// longest total contact first. Only the locations the subject was at are replayed.
+pub async fn contacts_for(
+    db: &DatabaseConnection,
+    resident: &residents::Model,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    min_overlap: Duration,
+) -> Result<Vec<Contact>, DbErr> {
+    let subject = subject_intervals(db, resident, from, to).await?;
+    let mut visited: Vec<i32> = subject.iter().map(|i| i.location).collect();
+    visited.sort_unstable();
+    visited.dedup();
+    if visited.is_empty() {
+        return Ok(Vec::new());
+    }
+    let (residents, intervals) = others_at(db, resident.id, &visited, from, to).await?;
+
+    let mut contacts: Vec<Contact> = residents
+        .into_iter()
+        .filter_map(|other| {
+            let theirs = intervals.get(&other.id)?;
+            let overlaps: Vec<Overlap> = subject
+                .iter()
+                .flat_map(|mine| theirs.iter().map(move |their| (mine, their)))
+                .filter(|(mine, their)| mine.location == their.location)
+                .filter_map(|(mine, their)| {
+                    let start = mine.start.max(their.start);
+                    let end = mine.end.min(their.end);
+                    (end > start && end - start >= min_overlap).then_some(Overlap {
+                        location: mine.location,
+                        start,
+                        end,
+                        seconds: (end - start).num_seconds(),
+                    })
+                })
+                .collect();
+            (!overlaps.is_empty()).then(|| Contact {
+                rfid: other.rfid,
+                name: other.name,
+                doc: other.doc,
+                total_seconds: overlaps.iter().map(|o| o.seconds).sum(),
+                overlaps,
+            })
+        })
+        .collect();
+    contacts.sort_by_key(|c| std::cmp::Reverse(c.total_seconds));
+    Ok(contacts)
+}
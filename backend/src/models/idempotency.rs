@@ -0,0 +1,112 @@
+use chrono::{Duration, Local, NaiveDateTime};
+use entity::idempotency_keys;
+use sea_orm::{
+    sea_query::OnConflict, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, Set,
+};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Header clients send to make a POST safe to retry
+pub const HEADER: &str = "Idempotency-Key";
+
+/// How long a key is remembered
+pub const KEEP_HOURS: i64 = 24;
+
+/// How often keys past `KEEP_HOURS` are deleted
+const EXPIRE_EVERY: std::time::Duration = std::time::Duration::from_secs(3600);
+
+#[derive(Debug)]
+pub enum Claim {
+    /// first time this key is seen, go ahead
+    New,
+    /// already answered, send the same status and body again
+    Replay(u16, Value),
+    /// claimed but never answered
+    InProgress,
+    /// already used for a request with a different body
+    Mismatch,
+}
+
+/// Hex SHA-256 of a request body, stored with its key
+pub fn request_hash(body: &[u8]) -> String {
+    hex::encode(Sha256::digest(body))
+}
+
+/// Claim `key` for the request hashing to `hash`. Inside a transaction a
+/// concurrent request with the same key waits on the row until the first one
+/// commits, then replays it.
+pub async fn claim<C: ConnectionTrait>(
+    db: &C,
+    key: &str,
+    hash: &str,
+    now: NaiveDateTime,
+) -> Result<Claim, DbErr> {
+    let inserted = idempotency_keys::Entity::insert(idempotency_keys::ActiveModel {
+        key: Set(key.to_owned()),
+        status: Set(None),
+        response: Set(None),
+        created_at: Set(now),
+        request_hash: Set(Some(hash.to_owned())),
+    })
+    .on_conflict(
+        OnConflict::column(idempotency_keys::Column::Key)
+            .do_nothing()
+            .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await?;
+    if inserted > 0 {
+        return Ok(Claim::New);
+    }
+    let existing = idempotency_keys::Entity::find_by_id(key.to_owned())
+        .one(db)
+        .await?;
+    Ok(match existing {
+        Some(idempotency_keys::Model {
+            request_hash: Some(stored),
+            ..
+        }) if stored != hash => Claim::Mismatch,
+        Some(idempotency_keys::Model {
+            status: Some(status),
+            response: Some(response),
+            ..
+        }) => Claim::Replay(status as u16, response),
+        _ => Claim::InProgress,
+    })
+}
+
+/// Store the answer given for a claimed key
+pub async fn save<C: ConnectionTrait>(
+    db: &C,
+    key: &str,
+    status: u16,
+    response: Value,
+) -> Result<(), DbErr> {
+    idempotency_keys::Entity::update_many()
+        .col_expr(idempotency_keys::Column::Status, i32::from(status).into())
+        .col_expr(idempotency_keys::Column::Response, response.into())
+        .filter(idempotency_keys::Column::Key.eq(key))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Forget keys older than `KEEP_HOURS`
+pub async fn expire<C: ConnectionTrait>(db: &C, now: NaiveDateTime) -> Result<u64, DbErr> {
+    let deleted = idempotency_keys::Entity::delete_many()
+        .filter(idempotency_keys::Column::CreatedAt.lt(now - Duration::hours(KEEP_HOURS)))
+        .exec(db)
+        .await?;
+    Ok(deleted.rows_affected)
+}
+
+/// Periodic task deleting expired keys, kept off the scans' transactions
+pub async fn run(db: DatabaseConnection) {
+    loop {
+        actix_web::rt::time::sleep(EXPIRE_EVERY).await;
+        if let Err(err) = expire(&db, Local::now().naive_local()).await {
+            log::error!("expiring idempotency keys: {}", err);
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use super::alerts::{Breach, OVER_CAPACITY};
+use super::schedules::active_schedule;
+use chrono::Local;
+use entity::sea_orm_active_enums::LocationKind;
+use entity::{
+    location_level_caps::{self, Entity as LocationLevelCaps},
+    location_schedules::{self, Entity as LocationSchedules},
+    locations::{self, Entity as Locations},
+    residents::{self, Entity as Residents},
+};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DbErr, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A location along with how many residents are currently there
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocationView {
+    #[serde(flatten)]
+    pub location: locations::Model,
+    pub occupancy: i64,
+    /// occupancy of this location and everything beneath it in the hierarchy
+    pub total_occupancy: i64,
+    pub level_caps: Vec<location_level_caps::Model>,
+    /// false only when the location has schedules and none covers right now
+    pub is_open: bool,
+    pub active_schedule: Option<location_schedules::Model>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LevelCap {
+    pub level: i32,
+    pub capacity: i32,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateLocation {
+    pub name: Option<String>,
+    pub level: Option<i32>,
+    /// `null` clears the capacity, omitting it leaves it unchanged
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub capacity: Option<Option<i32>>,
+    pub refuse_over_capacity: Option<bool>,
+    pub refuse_when_closed: Option<bool>,
+    pub debounce_seconds: Option<i32>,
+    /// `null` makes this a top level location
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub parent_id: Option<Option<i32>>,
+    pub kind: Option<LocationKind>,
+    /// replaces all per-level caps for the location when present
+    pub level_caps: Option<Vec<LevelCap>>,
+}
+
+impl UpdateLocation {
+    /// Why the changes were turned down
+    pub fn validate(&self) -> Result<(), String> {
+        if self.capacity.flatten().is_some_and(|capacity| capacity < 0) {
+            Err("Capacity can't be negative".to_string())
+        } else if self.level_caps.iter().flatten().any(|cap| cap.capacity < 0) {
+            Err("Level caps can't be negative".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Number of residents per `current_location`
+pub async fn occupancy_counts<C: ConnectionTrait>(db: &C) -> Result<HashMap<i32, i64>, DbErr> {
+    let counts: Vec<(i32, i64)> = Residents::find()
+        .select_only()
+        .column(residents::Column::CurrentLocation)
+        .column_as(residents::Column::Id.count(), "occupancy")
+        .group_by(residents::Column::CurrentLocation)
+        .into_tuple()
+        .all(db)
+        .await?;
+    Ok(counts.into_iter().collect())
+}
+
+/// Every location id at or below `id` in the hierarchy, starting with `id` itself
+pub fn descendants_of(locations: &[locations::Model], id: i32) -> Vec<i32> {
+    let mut children: HashMap<i32, Vec<i32>> = HashMap::new();
+    for loc in locations {
+        if let Some(parent) = loc.parent_id {
+            children.entry(parent).or_default().push(loc.id);
+        }
+    }
+    let mut ids = vec![id];
+    let mut next = 0;
+    while next < ids.len() {
+        for child in children.get(&ids[next]).into_iter().flatten() {
+            if !ids.contains(child) {
+                ids.push(*child);
+            }
+        }
+        next += 1;
+    }
+    ids
+}
+
+/// `id` plus all of its sub-locations, so queries can roll up to any ancestor
+pub async fn location_and_descendants<C: ConnectionTrait>(
+    db: &C,
+    id: i32,
+) -> Result<Vec<i32>, DbErr> {
+    let all = Locations::find().all(db).await?;
+    Ok(descendants_of(&all, id))
+}
+
+/// Locations ordered by id with their occupancy, or just `only` when given
+pub async fn location_views<C: ConnectionTrait>(
+    db: &C,
+    only: Option<i32>,
+) -> Result<Vec<LocationView>, DbErr> {
+    let all = Locations::find()
+        .order_by_asc(locations::Column::Id)
+        .all(db)
+        .await?;
+    let counts = occupancy_counts(db).await?;
+    let mut caps: HashMap<i32, Vec<location_level_caps::Model>> = HashMap::new();
+    let mut cap_query = LocationLevelCaps::find();
+    if let Some(id) = only {
+        cap_query = cap_query.filter(location_level_caps::Column::LocationId.eq(id));
+    }
+    for cap in cap_query.all(db).await? {
+        caps.entry(cap.location_id).or_default().push(cap);
+    }
+    let mut schedules: HashMap<i32, Vec<location_schedules::Model>> = HashMap::new();
+    let mut schedule_query = LocationSchedules::find();
+    if let Some(id) = only {
+        schedule_query = schedule_query.filter(location_schedules::Column::LocationId.eq(id));
+    }
+    for schedule in schedule_query.all(db).await? {
+        schedules
+            .entry(schedule.location_id)
+            .or_default()
+            .push(schedule);
+    }
+    let now = Local::now().naive_local();
+    Ok(all
+        .iter()
+        .filter(|location| only.is_none_or(|id| location.id == id))
+        .map(|location| {
+            let schedules = schedules.remove(&location.id).unwrap_or_default();
+            let active = active_schedule(&schedules, now).cloned();
+            LocationView {
+                occupancy: counts.get(&location.id).copied().unwrap_or(0),
+                total_occupancy: descendants_of(&all, location.id)
+                    .iter()
+                    .filter_map(|id| counts.get(id))
+                    .sum(),
+                level_caps: caps.remove(&location.id).unwrap_or_default(),
+                is_open: schedules.is_empty() || active.is_some(),
+                active_schedule: active,
+                location: location.clone(),
+            }
+        })
+        .collect())
+}
+
+/// Check whether `resident` arriving at `location` puts it over its capacity or
+/// over the cap for the resident's level. The location decides whether the
+/// movement is refused. Locks the location's row, so arrivals at one location
+/// are counted one at a time.
+pub async fn check_capacity<C: ConnectionTrait>(
+    db: &C,
+    resident: &residents::Model,
+    location: i32,
+) -> Result<Option<Breach>, DbErr> {
+    // each arrival only holds its own resident's lock, without this two of
+    // them could both count the location before either has moved in
+    let Some(loc) = Locations::find_by_id(location)
+        .lock_exclusive()
+        .one(db)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let others = Residents::find()
+        .filter(residents::Column::CurrentLocation.eq(location))
+        .filter(residents::Column::Id.ne(resident.id));
+    let mut over = None;
+    if let Some(capacity) = loc.capacity {
+        let present = others.clone().count(db).await? + 1;
+        if present > capacity as u64 {
+            over = Some(format!("capacity {} of {}", present, capacity));
+        }
+    }
+    if over.is_none() {
+        let level_cap = LocationLevelCaps::find()
+            .filter(location_level_caps::Column::LocationId.eq(location))
+            .filter(location_level_caps::Column::Level.eq(resident.level))
+            .one(db)
+            .await?;
+        if let Some(cap) = level_cap {
+            let present = others
+                .filter(residents::Column::Level.eq(resident.level))
+                .count(db)
+                .await?
+                + 1;
+            if present > cap.capacity as u64 {
+                over = Some(format!(
+                    "level {} cap {} of {}",
+                    resident.level, present, cap.capacity
+                ));
+            }
+        }
+    }
+    let Some(over) = over else {
+        return Ok(None);
+    };
+    let action = if loc.refuse_over_capacity {
+        "refused entry to"
+    } else {
+        "arrived at"
+    };
+    let message = format!(
+        "Over capacity: {} ({}) {} {} ({})",
+        resident.name, resident.doc, action, loc.name, over
+    );
+    Ok(Some(Breach {
+        refuse: loc.refuse_over_capacity,
+        kind: OVER_CAPACITY,
+        resident_id: resident.id,
+        location,
+        message,
+    }))
+}
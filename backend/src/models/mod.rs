@@ -3,3 +3,37 @@ pub mod residents;
 pub mod timestamps;
 
 pub mod response;
+
+pub mod occupancy;
+
+pub mod contacts;
+
+pub mod alerts;
+
+pub mod separations;
+
+pub mod locations;
+
+pub mod schedules;
+
+pub mod appointments;
+
+pub mod shifts;
+
+pub mod consistency;
+
+pub mod movements;
+
+pub mod idempotency;
+
+pub mod badges;
+
+pub mod webhooks;
+
+pub mod rules;
+
+pub mod away;
+
+pub mod search;
+
+pub mod notes;
@@ -0,0 +1,472 @@
+use super::{
+    alerts::{self, Breach},
+    appointments::mark_arrival,
+    badges::canonical,
+    idempotency::{self, Claim},
+    locations::check_capacity,
+    notes,
+    response::Response,
+    rules,
+    schedules::check_schedule,
+    separations::check_separations,
+    timestamps::PostTimestamp,
+    timestamps::{Refusal, ResidentTimestamp},
+    webhooks,
+};
+use chrono::{Duration, Local, NaiveDateTime};
+use entity::{locations, residents, timestamps};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    IntoActiveModel, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// What became of a single scan
+#[derive(Debug)]
+pub enum ScanOutcome {
+    Recorded(ResidentTimestamp),
+    /// a repeat inside the location's debounce window, or a scan already on
+    /// record with the same scanner and time; carries the movement it repeated
+    Ignored(ResidentTimestamp),
+    /// older than the resident's latest movement, added to their history only
+    Backfilled(ResidentTimestamp),
+    /// refused by a capacity, schedule or clock rule, the alert has been raised
+    Refused(String, Refusal),
+    /// the badge didn't decode, see [`canonical`]
+    InvalidBadge(String),
+    UnknownResident,
+}
+
+impl ScanOutcome {
+    /// What the debouncer should remember about this scan once it is committed
+    pub fn seen(&self, scan: &PostTimestamp) -> Option<Seen> {
+        match self {
+            ScanOutcome::Recorded(movement) => Some(Seen {
+                rfid: movement.resident.rfid.clone(),
+                scanner: scanner_key(scan),
+                at: movement.timestamp.ts,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// A movement the debouncer has yet to hear about
+#[derive(Debug)]
+pub struct Seen {
+    rfid: String,
+    scanner: String,
+    at: NaiveDateTime,
+}
+
+/// Last accepted scan of each badge at each scanner, shared by the workers
+#[derive(Debug, Default)]
+pub struct Debouncer(Mutex<HashMap<(String, String), NaiveDateTime>>);
+
+impl Debouncer {
+    /// true when the badge was accepted at this scanner less than `window` ago
+    pub fn is_repeat(
+        &self,
+        rfid: &str,
+        scanner: &str,
+        at: NaiveDateTime,
+        window: Duration,
+    ) -> bool {
+        let seen = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        seen.get(&(rfid.to_owned(), scanner.to_owned()))
+            .is_some_and(|last| at >= *last && at - *last < window)
+    }
+
+    /// Remember a scan as the latest of its badge at its scanner. Only called
+    /// once the movement is committed, so a scan that was refused or rolled
+    /// back never makes the next one look like a repeat.
+    pub fn remember(&self, seen: Seen) {
+        let mut last_seen = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // nothing stays interesting much past the widest window, an hour is plenty
+        last_seen.retain(|_, last| seen.at - *last < Duration::hours(1));
+        last_seen.insert((seen.rfid, seen.scanner), seen.at);
+    }
+}
+
+/// How far a client's `scanned_at` may stray from the server clock. Outside
+/// the window the scan is refused, or recorded at the time it arrived and
+/// flagged with a `clock_skew` alert. `None` puts no limit on how far behind.
+#[derive(Debug, Clone)]
+pub struct ScanClock {
+    pub max_ahead: Duration,
+    pub max_behind: Option<Duration>,
+    /// Batches come from kiosks that were offline for as long as they were, so by default they have no limit
+    pub batch_max_behind: Option<Duration>,
+    pub refuse: bool,
+}
+
+impl Default for ScanClock {
+    fn default() -> Self {
+        Self {
+            max_ahead: Duration::seconds(60),
+            max_behind: Some(Duration::hours(24)),
+            batch_max_behind: None,
+            refuse: false,
+        }
+    }
+}
+
+impl ScanClock {
+    /// SCAN_MAX_AHEAD_SECONDS, SCAN_MAX_BEHIND_SECONDS, SCAN_BATCH_MAX_BEHIND_SECONDS and SCAN_SKEW_POLICY=flag|reject
+    pub fn from_env() -> Self {
+        let seconds = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.parse::<i64>().ok())
+        };
+        let default = Self::default();
+        Self {
+            max_ahead: seconds("SCAN_MAX_AHEAD_SECONDS")
+                .map_or(default.max_ahead, Duration::seconds),
+            max_behind: seconds("SCAN_MAX_BEHIND_SECONDS")
+                .map_or(default.max_behind, |limit| Some(Duration::seconds(limit))),
+            batch_max_behind: seconds("SCAN_BATCH_MAX_BEHIND_SECONDS")
+                .map_or(default.batch_max_behind, |limit| {
+                    Some(Duration::seconds(limit))
+                }),
+            refuse: std::env::var("SCAN_SKEW_POLICY")
+                .is_ok_and(|policy| policy.eq_ignore_ascii_case("reject")),
+        }
+    }
+
+    /// The window batch uploads are checked against
+    pub fn for_batch(&self) -> Self {
+        Self {
+            max_behind: self.batch_max_behind,
+            ..self.clone()
+        }
+    }
+
+    /// Describes the problem when `scanned_at` is outside the window
+    pub fn check(&self, scanned_at: NaiveDateTime, received_at: NaiveDateTime) -> Option<String> {
+        if scanned_at > received_at + self.max_ahead {
+            Some(format!(
+                "Scan time {} is {} seconds ahead of the server clock",
+                scanned_at,
+                (scanned_at - received_at).num_seconds()
+            ))
+        } else if self
+            .max_behind
+            .is_some_and(|max_behind| scanned_at < received_at - max_behind)
+        {
+            Some(format!(
+                "Scan time {} is {} seconds behind the server clock",
+                scanned_at,
+                (received_at - scanned_at).num_seconds()
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Scans without a scanner id are debounced per location
+pub fn scanner_key(scan: &PostTimestamp) -> String {
+    match &scan.scanner {
+        Some(scanner) => scanner.clone(),
+        None => format!("location-{}", scan.location),
+    }
+}
+
+/// Apply one scan: lock the resident, run the location rules, toggle their
+/// location and record the timestamp at the scan's `scanned_at`, or now when
+/// the client didn't send one. Meant to run inside a transaction.
+pub async fn record_scan<C: ConnectionTrait>(
+    db: &C,
+    scan: &PostTimestamp,
+    debouncer: &Debouncer,
+    clock: &ScanClock,
+) -> Result<ScanOutcome, DbErr> {
+    let rfid = match canonical(&scan.rfid) {
+        Ok(rfid) => rfid,
+        Err(err) => return Ok(ScanOutcome::InvalidBadge(err.to_string())),
+    };
+    let Some(resident) = residents::Entity::find()
+        .filter(residents::Column::Rfid.eq(rfid.clone()))
+        .lock_exclusive()
+        .one(db)
+        .await?
+    else {
+        return Ok(ScanOutcome::UnknownResident);
+    };
+    // read the clock only once the lock is held, so timestamps of one resident
+    // are in the order their scans were applied
+    let received_at = Local::now().naive_local();
+    let flags = notes::active_flags(db, resident.id, received_at).await?;
+    if let Some(scanned_at) = scan.scanned_at {
+        // a kiosk retrying an upload resends scans that are already on record
+        let stored = timestamps::Entity::find()
+            .filter(timestamps::Column::Rfid.eq(resident.id))
+            .filter(timestamps::Column::ScannedAt.eq(scanned_at))
+            .filter(match &scan.scanner {
+                Some(scanner) => timestamps::Column::Scanner.eq(scanner.clone()),
+                None => timestamps::Column::Scanner.is_null(),
+            })
+            .one(db)
+            .await?;
+        if let Some(stored) = stored {
+            return Ok(ScanOutcome::Ignored(ResidentTimestamp {
+                flags,
+                ..ResidentTimestamp::new(resident, stored)
+            }));
+        }
+    }
+    // anything only worth flagging is raised once the scan is known to be accepted
+    let mut warnings = Vec::new();
+    let now = match scan.scanned_at {
+        None => received_at,
+        Some(scanned_at) => match clock.check(scanned_at, received_at) {
+            None => scanned_at,
+            Some(message) => {
+                let skew = Breach {
+                    refuse: clock.refuse,
+                    kind: alerts::CLOCK_SKEW,
+                    resident_id: resident.id,
+                    location: scan.location,
+                    message,
+                };
+                if skew.refuse {
+                    let message = skew.raise(db).await?.message;
+                    return Ok(ScanOutcome::Refused(message, Refusal { resident, flags }));
+                }
+                // the client's clock can't be trusted, go by when it reached us
+                warnings.push(skew);
+                received_at
+            }
+        },
+    };
+    let latest = timestamps::Entity::find()
+        .filter(timestamps::Column::Rfid.eq(resident.id))
+        .order_by_desc(timestamps::Column::Ts)
+        .order_by_desc(timestamps::Column::Id)
+        .one(db)
+        .await?;
+    let debounce: i32 = locations::Entity::find_by_id(scan.location)
+        .select_only()
+        .column(locations::Column::DebounceSeconds)
+        .into_tuple()
+        .one(db)
+        .await?
+        .unwrap_or(0);
+    if debounce > 0
+        && debouncer.is_repeat(
+            &rfid,
+            &scanner_key(scan),
+            now,
+            Duration::seconds(debounce.into()),
+        )
+    {
+        if let Some(latest) = &latest {
+            return Ok(ScanOutcome::Ignored(ResidentTimestamp {
+                flags,
+                ..ResidentTimestamp::new(resident, latest.clone())
+            }));
+        }
+    }
+    if latest.as_ref().is_some_and(|latest| latest.ts > now) {
+        // a newer movement is already on record, so this one only fills in
+        // history and leaves where the resident is now alone. It toggles
+        // against where they were at the time, as it would have live.
+        let before: Option<i32> = timestamps::Entity::find()
+            .select_only()
+            .column(timestamps::Column::Location)
+            .filter(timestamps::Column::Rfid.eq(resident.id))
+            .filter(timestamps::Column::Ts.lte(now))
+            .order_by_desc(timestamps::Column::Ts)
+            .order_by_desc(timestamps::Column::Id)
+            .into_tuple()
+            .one(db)
+            .await?;
+        let location = if before == Some(scan.location) {
+            0
+        } else {
+            scan.location
+        };
+        let backfilled = timestamps::ActiveModel {
+            rfid: Set(resident.id),
+            location: Set(location),
+            ts: Set(now),
+            received_at: Set(received_at),
+            scanner: Set(scan.scanner.clone()),
+            scanned_at: Set(scan.scanned_at),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+        return Ok(ScanOutcome::Backfilled(ResidentTimestamp {
+            resident,
+            timestamp: backfilled,
+            alerts: raise_all(db, warnings).await?,
+            flags,
+        }));
+    }
+
+    if scan.location != resident.current_location {
+        let breaches: Vec<Breach> = [
+            check_capacity(db, &resident, scan.location).await?,
+            check_schedule(db, &resident, scan.location, now).await?,
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        // every check runs before anything is raised, so a refused movement
+        // only leaves the alerts that refused it
+        let (refusals, flagged): (Vec<Breach>, Vec<Breach>) =
+            breaches.into_iter().partition(|breach| breach.refuse);
+        if !refusals.is_empty() {
+            let messages: Vec<String> = raise_all(db, refusals)
+                .await?
+                .into_iter()
+                .map(|alert| alert.message)
+                .collect();
+            return Ok(ScanOutcome::Refused(
+                messages.join("; "),
+                Refusal { resident, flags },
+            ));
+        }
+        warnings.extend(flagged);
+    }
+    let mut alerts = raise_all(db, warnings).await?;
+    let previous_location = resident.current_location;
+    let mut resident = resident.into_active_model();
+    if scan.location == resident.current_location.to_owned().unwrap() {
+        resident.current_location = Set(0);
+    } else {
+        resident.current_location = Set(scan.location);
+    }
+
+    let updated_resident = resident.update(db).await?;
+    let new_ts = timestamps::ActiveModel {
+        rfid: Set(updated_resident.id),
+        location: Set(updated_resident.current_location),
+        ts: Set(now),
+        received_at: Set(received_at),
+        scanner: Set(scan.scanner.clone()),
+        scanned_at: Set(scan.scanned_at),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    // the movement stands either way, conflicts only come back as alerts
+    alerts.extend(check_separations(db, &updated_resident).await?);
+    mark_arrival(db, &updated_resident, new_ts.ts).await?;
+    alerts.extend(rules::on_movement(db, &updated_resident, previous_location, now).await?);
+    let movement = ResidentTimestamp {
+        resident: updated_resident,
+        timestamp: new_ts,
+        alerts,
+        flags,
+    };
+    // flags are officer notes for the kiosk, not for other systems
+    let payload = ResidentTimestamp {
+        flags: Vec::new(),
+        ..movement.clone()
+    };
+    webhooks::enqueue(db, webhooks::MOVEMENT, &payload).await?;
+    if payload.timestamp.location == 0 {
+        webhooks::enqueue(db, webhooks::AWAY, &payload).await?;
+    }
+    Ok(ScanOutcome::Recorded(movement))
+}
+
+async fn raise_all<C: ConnectionTrait>(
+    db: &C,
+    breaches: Vec<Breach>,
+) -> Result<Vec<entity::alerts::Model>, DbErr> {
+    let mut raised = Vec::with_capacity(breaches.len());
+    for breach in breaches {
+        raised.push(breach.raise(db).await?);
+    }
+    Ok(raised)
+}
+
+/// One scan from start to finish as POST /api/timestamps answers it: claim the
+/// idempotency key if there is one, record the scan and commit. Returns the
+/// HTTP status and response body, which is also what a replay gets.
+pub async fn apply_scan(
+    db: &DatabaseConnection,
+    scan: &PostTimestamp,
+    key: Option<&str>,
+    debouncer: &Debouncer,
+    clock: &ScanClock,
+) -> Result<(u16, Value), Box<dyn std::error::Error>> {
+    // the whole movement is one transaction holding the resident's row lock, so
+    // concurrent scans of the same badge are applied one after the other
+    let txn = db.begin().await?;
+    if let Some(key) = key {
+        // the key is bound to the scan it was first sent with
+        let hash = idempotency::request_hash(&serde_json::to_vec(scan)?);
+        match idempotency::claim(&txn, key, &hash, Local::now().naive_local()).await? {
+            Claim::New => {}
+            Claim::Replay(status, body) => {
+                txn.rollback().await?;
+                return Ok((status, body));
+            }
+            Claim::InProgress => {
+                txn.rollback().await?;
+                let error_resp: Response<String> =
+                    Response::from_error("Idempotency key is already in use");
+                return Ok((409, serde_json::to_value(error_resp)?));
+            }
+            Claim::Mismatch => {
+                txn.rollback().await?;
+                let error_resp: Response<String> =
+                    Response::from_error("Idempotency key was already used for a different scan");
+                return Ok((422, serde_json::to_value(error_resp)?));
+            }
+        }
+    }
+    let outcome = record_scan(&txn, scan, debouncer, clock).await?;
+    let seen = outcome.seen(scan);
+    let (status, body) = match outcome {
+        ScanOutcome::Recorded(movement) | ScanOutcome::Backfilled(movement) => (
+            201,
+            serde_json::to_value(Response::<ResidentTimestamp>::from(movement))?,
+        ),
+        ScanOutcome::Ignored(movement) => {
+            let mut response = Response::<ResidentTimestamp>::from(movement);
+            response.message = String::from("Duplicate scan ignored");
+            (200, serde_json::to_value(response)?)
+        }
+        // a refusal keeps its alert, nothing else has changed
+        ScanOutcome::Refused(message, refusal) => {
+            let response = Response {
+                success: false,
+                message,
+                data: Some(vec![refusal]),
+            };
+            (200, serde_json::to_value(response)?)
+        }
+        ScanOutcome::InvalidBadge(message) => (
+            200,
+            serde_json::to_value(Response::<String>::from_error(&message))?,
+        ),
+        ScanOutcome::UnknownResident => (
+            200,
+            serde_json::to_value(Response::<String>::from_error(
+                "Error retrieving resident: Not found in system, please add Resident.",
+            ))?,
+        ),
+    };
+    if let Some(key) = key {
+        idempotency::save(&txn, key, status, body.clone()).await?;
+    }
+    txn.commit().await?;
+    if let Some(seen) = seen {
+        debouncer.remember(seen);
+    }
+    Ok((status, body))
+}
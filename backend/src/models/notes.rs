@@ -0,0 +1,97 @@
+use chrono::NaiveDateTime;
+use entity::{
+    resident_notes::{self, Entity as ResidentNotes},
+    sea_orm_active_enums::NoteSeverity,
+};
+use sea_orm::{
+    sea_query::SimpleExpr, ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait,
+    QueryFilter, QueryOrder,
+};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct NewNote {
+    pub author: String,
+    pub category: String,
+    pub note: String,
+    #[serde(default)]
+    pub severity: NoteSeverity,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+/// `"expires_at": null` makes a note permanent, omitting it leaves it unchanged
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateNote {
+    pub category: Option<String>,
+    pub note: Option<String>,
+    pub severity: Option<NoteSeverity>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub expires_at: Option<Option<NaiveDateTime>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct NoteParams {
+    /// also list notes that have expired
+    #[serde(default)]
+    pub include_expired: bool,
+    pub severity: Option<NoteSeverity>,
+}
+
+/// Why a note was turned down
+pub fn validate(note: &resident_notes::Model) -> Result<(), String> {
+    if note.author.trim().is_empty() {
+        Err("A note needs an author".to_string())
+    } else if note.category.trim().is_empty() {
+        Err("A note needs a category".to_string())
+    } else if note.note.trim().is_empty() {
+        Err("A note can't be empty".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// Notes that haven't expired by `now`
+pub fn active(now: NaiveDateTime) -> Condition {
+    Condition::any()
+        .add(resident_notes::Column::ExpiresAt.is_null())
+        .add(resident_notes::Column::ExpiresAt.gt(now))
+}
+
+fn for_resident(resident_id: i32) -> SimpleExpr {
+    resident_notes::Column::ResidentId.eq(resident_id)
+}
+
+/// A resident's notes, newest first
+pub async fn notes_for<C: ConnectionTrait>(
+    db: &C,
+    resident_id: i32,
+    params: &NoteParams,
+    now: NaiveDateTime,
+) -> Result<Vec<resident_notes::Model>, DbErr> {
+    let mut query = ResidentNotes::find().filter(for_resident(resident_id));
+    if !params.include_expired {
+        query = query.filter(active(now));
+    }
+    if let Some(severity) = params.severity {
+        query = query.filter(resident_notes::Column::Severity.eq(severity));
+    }
+    query
+        .order_by_desc(resident_notes::Column::CreatedAt)
+        .order_by_desc(resident_notes::Column::Id)
+        .all(db)
+        .await
+}
+
+/// The high severity notes in force on a resident, which go out with every
+/// movement so the kiosk can show them when the badge is scanned
+pub async fn active_flags<C: ConnectionTrait>(
+    db: &C,
+    resident_id: i32,
+    now: NaiveDateTime,
+) -> Result<Vec<resident_notes::Model>, DbErr> {
+    let params = NoteParams {
+        severity: Some(NoteSeverity::High),
+        ..Default::default()
+    };
+    notes_for(db, resident_id, &params, now).await
+}
@@ -0,0 +1,92 @@
+use chrono::NaiveDateTime;
+use sea_orm::{DatabaseConnection, DbBackend, DbErr, FromQueryResult, Statement};
+use serde::{Deserialize, Deserializer, Serialize};
+
+// Latest timestamp per resident at or before $1, served by idx_timestamps_rfid_ts
+const OCCUPANTS_AT: &str = r#"
+SELECT r.rfid, r.name, r.doc, r.room, r.unit, r.level, t.location, t.ts AS since
+FROM residents r
+CROSS JOIN LATERAL (
+    SELECT location, ts FROM timestamps
+    WHERE timestamps.rfid = r.id AND timestamps.ts <= $1
+    ORDER BY timestamps.ts DESC, timestamps.id DESC
+    LIMIT 1
+) t"#;
+
+#[derive(Debug, Deserialize)]
+pub struct AtParams {
+    #[serde(deserialize_with = "deserialize_datetime")]
+    pub at: NaiveDateTime,
+    /// include sub-locations, defaults to false
+    pub rollup: Option<bool>,
+}
+
+/// A resident's reconstructed location at a point in time, and when they arrived there
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromQueryResult)]
+pub struct Occupant {
+    pub rfid: String,
+    pub name: String,
+    pub doc: String,
+    pub room: String,
+    pub unit: i32,
+    pub level: i32,
+    pub location: i32,
+    pub since: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocationSnapshot {
+    pub location: i32,
+    pub name: String,
+    pub occupants: Vec<Occupant>,
+}
+
+// Accepts "2023-11-21T14:32:00", "2023-11-21 14:32:00" or without seconds
+pub fn deserialize_datetime<'de, D>(deserializer: D) -> Result<NaiveDateTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let date_str = String::deserialize(deserializer)?;
+    parse_datetime(&date_str).map_err(serde::de::Error::custom)
+}
+
+// As deserialize_datetime, for optional fields (pair with #[serde(default)])
+pub fn deserialize_optional_datetime<'de, D>(
+    deserializer: D,
+) -> Result<Option<NaiveDateTime>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_datetime(deserializer).map(Some)
+}
+
+pub fn parse_datetime(date_str: &str) -> Result<NaiveDateTime, chrono::ParseError> {
+    let date_str = date_str.trim().replace('T', " ");
+    NaiveDateTime::parse_from_str(&date_str, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(&date_str, "%Y-%m-%d %H:%M"))
+}
+
+/// Replay the timestamps table to find where every resident was at `at`,
+/// optionally restricted to a set of locations.
+pub async fn occupants_at(
+    db: &DatabaseConnection,
+    at: NaiveDateTime,
+    locations: Option<&[i32]>,
+) -> Result<Vec<Occupant>, DbErr> {
+    let stmt = match locations {
+        Some(locations) => Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            format!(
+                "{} WHERE t.location = ANY($2) ORDER BY r.name",
+                OCCUPANTS_AT
+            ),
+            [at.into(), locations.to_vec().into()],
+        ),
+        None => Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            format!("{} ORDER BY t.location, r.name", OCCUPANTS_AT),
+            [at.into()],
+        ),
+    };
+    Occupant::find_by_statement(stmt).all(db).await
+}
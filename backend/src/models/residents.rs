@@ -1,6 +1,14 @@
+use super::badges;
 use actix_web::error::BlockingError;
 use actix_web::ResponseError;
-use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use entity::residents::{self, Entity as Residents};
+use sea_orm::{
+    sea_query::SimpleExpr, ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait,
+    IntoActiveModel, QueryFilter, Set, SqlErr,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt::{Display, Formatter};
 
 #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct UpdateResident {
@@ -13,10 +21,93 @@ pub struct UpdateResident {
     pub level: Option<usize>,
 }
 
-use std::fmt::{Display, Formatter};
+impl UpdateResident {
+    /// `resident` with the fields that were sent changed
+    pub fn apply(self, resident: residents::Model) -> residents::ActiveModel {
+        let mut resident = resident.into_active_model();
+        if let Some(rfid) = self.rfid {
+            resident.rfid = Set(rfid);
+        }
+        if let Some(name) = self.name {
+            resident.name = Set(name);
+        }
+        if let Some(doc) = self.doc {
+            resident.doc = Set(normalize_doc(&doc));
+        }
+        if let Some(room) = self.room {
+            resident.room = Set(room);
+        }
+        if let Some(unit) = self.unit {
+            resident.unit = Set(unit as i32);
+        }
+        if let Some(current_location) = self.current_location {
+            resident.current_location = Set(current_location as i32);
+        }
+        if let Some(level) = self.level {
+            resident.level = Set(level as i32);
+        }
+        resident
+    }
+}
 
-use chrono::NaiveDate;
-use serde::Deserializer;
+/// How a route names the resident it is about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResidentKey {
+    /// a badge, in any format the readers send
+    Rfid(String),
+    Doc(String),
+    /// the resident's `id`, which stays the same when their badge is replaced
+    Id(i32),
+}
+
+impl ResidentKey {
+    pub fn filter(&self) -> SimpleExpr {
+        match self {
+            ResidentKey::Rfid(rfid) => badges::matches(rfid),
+            ResidentKey::Doc(doc) => residents::Column::Doc.eq(normalize_doc(doc)),
+            ResidentKey::Id(id) => residents::Column::Id.eq(*id),
+        }
+    }
+}
+
+pub async fn find_resident<C: ConnectionTrait>(
+    db: &C,
+    key: &ResidentKey,
+) -> Result<Option<residents::Model>, DbErr> {
+    Residents::find().filter(key.filter()).one(db).await
+}
+
+/// DOC numbers are compared without surrounding whitespace
+pub fn normalize_doc(doc: &str) -> String {
+    doc.trim().to_string()
+}
+
+/// What to tell the caller when a write hit the unique badge or DOC number
+pub fn conflict(err: &DbErr, doc: &str) -> Option<String> {
+    match err.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(detail)) if detail.contains("doc") => Some(format!(
+            "DOC number {} already belongs to another resident",
+            doc
+        )),
+        Some(SqlErr::UniqueConstraintViolation(_)) => {
+            Some("Badge already belongs to another resident".to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Save a changed resident, or say why not when the new badge or DOC number
+/// already belongs to someone else
+pub async fn save_resident<C: ConnectionTrait>(
+    db: &C,
+    resident: residents::ActiveModel,
+) -> Result<Result<residents::Model, String>, DbErr> {
+    let doc = resident.doc.clone().unwrap();
+    match resident.update(db).await {
+        Ok(saved) => Ok(Ok(saved)),
+        Err(err) => conflict(&err, &doc).map(Err).ok_or(err),
+    }
+}
 
 #[derive(Debug, Deserialize)]
 pub struct PathParams {
@@ -1,4 +1,13 @@
-use super::timestamps::{PostTimestamp, ResidentTimestamp};
+use super::appointments::AppointmentView;
+use super::away::AwayResident;
+use super::consistency::Discrepancy;
+use super::contacts::Contact;
+use super::locations::LocationView;
+use super::occupancy::{LocationSnapshot, Occupant};
+use super::search::ResidentMatch;
+use super::separations::SeparationView;
+use super::shifts::ShiftRun;
+use super::timestamps::{BatchResult, PostTimestamp, Refusal, ResidentTimestamp};
 use actix_web::ResponseError;
 use entity::locations;
 use entity::prelude::OrmSerializable as Serializable;
@@ -7,6 +16,18 @@ use std::fmt::{Display, Formatter};
 
 impl Serializable for PostTimestamp {}
 impl Serializable for ResidentTimestamp {}
+impl Serializable for Refusal {}
+impl Serializable for Occupant {}
+impl Serializable for LocationSnapshot {}
+impl Serializable for Contact {}
+impl Serializable for SeparationView {}
+impl Serializable for LocationView {}
+impl Serializable for AppointmentView {}
+impl Serializable for ShiftRun {}
+impl Serializable for Discrepancy {}
+impl Serializable for BatchResult {}
+impl Serializable for AwayResident {}
+impl Serializable for ResidentMatch {}
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Response<T> {
@@ -129,6 +150,387 @@ where
     }
 }
 
+impl<T> From<Vec<Occupant>> for Response<T>
+where
+    T: From<Occupant> + Serializable,
+    Vec<T>: From<Vec<Occupant>>,
+{
+    fn from(value: Vec<Occupant>) -> Self {
+        Self {
+            success: true,
+            message: "Occupants successfully retrieved".to_string(),
+            data: Some(value.into()),
+        }
+    }
+}
+
+impl<T> From<Vec<LocationSnapshot>> for Response<T>
+where
+    T: From<LocationSnapshot> + Serializable,
+    Vec<T>: From<Vec<LocationSnapshot>>,
+{
+    fn from(value: Vec<LocationSnapshot>) -> Self {
+        Self {
+            success: true,
+            message: "Snapshot successfully retrieved".to_string(),
+            data: Some(value.into()),
+        }
+    }
+}
+
+impl<T> From<Vec<Contact>> for Response<T>
+where
+    T: From<Contact> + Serializable,
+    Vec<T>: From<Vec<Contact>>,
+{
+    fn from(value: Vec<Contact>) -> Self {
+        Self {
+            success: true,
+            message: "Contacts successfully retrieved".to_string(),
+            data: Some(value.into()),
+        }
+    }
+}
+
+impl<T> From<LocationView> for Response<T>
+where
+    T: From<LocationView> + Serializable,
+{
+    fn from(value: LocationView) -> Self {
+        Self {
+            success: true,
+            message: "Location successfully retrieved".to_string(),
+            data: Some(vec![T::from(value)]),
+        }
+    }
+}
+
+impl<T> From<Vec<LocationView>> for Response<T>
+where
+    T: From<LocationView> + Serializable,
+    Vec<T>: From<Vec<LocationView>>,
+{
+    fn from(value: Vec<LocationView>) -> Self {
+        Self {
+            success: true,
+            message: "Location successfully retrieved".to_string(),
+            data: Some(value.into()),
+        }
+    }
+}
+
+impl<T> From<SeparationView> for Response<T>
+where
+    T: From<SeparationView> + Serializable,
+{
+    fn from(value: SeparationView) -> Self {
+        Self {
+            success: true,
+            message: "Separation successfully retrieved".to_string(),
+            data: Some(vec![T::from(value)]),
+        }
+    }
+}
+
+impl<T> From<Vec<SeparationView>> for Response<T>
+where
+    T: From<SeparationView> + Serializable,
+    Vec<T>: From<Vec<SeparationView>>,
+{
+    fn from(value: Vec<SeparationView>) -> Self {
+        Self {
+            success: true,
+            message: "Separations successfully retrieved".to_string(),
+            data: Some(value.into()),
+        }
+    }
+}
+
+impl<T> From<Vec<entity::alerts::Model>> for Response<T>
+where
+    T: From<entity::alerts::Model> + Serializable,
+    Vec<T>: From<Vec<entity::alerts::Model>>,
+{
+    fn from(value: Vec<entity::alerts::Model>) -> Self {
+        Self {
+            success: true,
+            message: "Alerts successfully retrieved".to_string(),
+            data: Some(value.into()),
+        }
+    }
+}
+
+impl<T> From<entity::alerts::Model> for Response<T>
+where
+    T: From<entity::alerts::Model> + Serializable,
+{
+    fn from(value: entity::alerts::Model) -> Self {
+        Self {
+            success: true,
+            message: "Alert successfully retrieved".to_string(),
+            data: Some(vec![T::from(value)]),
+        }
+    }
+}
+
+impl<T> From<entity::rules::Model> for Response<T>
+where
+    T: From<entity::rules::Model> + Serializable,
+{
+    fn from(value: entity::rules::Model) -> Self {
+        Self {
+            success: true,
+            message: "Rule successfully retrieved".to_string(),
+            data: Some(vec![T::from(value)]),
+        }
+    }
+}
+
+impl<T> From<Vec<entity::rules::Model>> for Response<T>
+where
+    T: From<entity::rules::Model> + Serializable,
+    Vec<T>: From<Vec<entity::rules::Model>>,
+{
+    fn from(value: Vec<entity::rules::Model>) -> Self {
+        Self {
+            success: true,
+            message: "Rules successfully retrieved".to_string(),
+            data: Some(value.into()),
+        }
+    }
+}
+
+impl<T> From<entity::resident_notes::Model> for Response<T>
+where
+    T: From<entity::resident_notes::Model> + Serializable,
+{
+    fn from(value: entity::resident_notes::Model) -> Self {
+        Self {
+            success: true,
+            message: "Note successfully retrieved".to_string(),
+            data: Some(vec![T::from(value)]),
+        }
+    }
+}
+
+impl<T> From<Vec<entity::resident_notes::Model>> for Response<T>
+where
+    T: From<entity::resident_notes::Model> + Serializable,
+    Vec<T>: From<Vec<entity::resident_notes::Model>>,
+{
+    fn from(value: Vec<entity::resident_notes::Model>) -> Self {
+        Self {
+            success: true,
+            message: "Notes successfully retrieved".to_string(),
+            data: Some(value.into()),
+        }
+    }
+}
+
+impl<T> From<entity::location_schedules::Model> for Response<T>
+where
+    T: From<entity::location_schedules::Model> + Serializable,
+{
+    fn from(value: entity::location_schedules::Model) -> Self {
+        Self {
+            success: true,
+            message: "Schedule successfully retrieved".to_string(),
+            data: Some(vec![T::from(value)]),
+        }
+    }
+}
+
+impl<T> From<Vec<entity::location_schedules::Model>> for Response<T>
+where
+    T: From<entity::location_schedules::Model> + Serializable,
+    Vec<T>: From<Vec<entity::location_schedules::Model>>,
+{
+    fn from(value: Vec<entity::location_schedules::Model>) -> Self {
+        Self {
+            success: true,
+            message: "Schedules successfully retrieved".to_string(),
+            data: Some(value.into()),
+        }
+    }
+}
+
+impl<T> From<AppointmentView> for Response<T>
+where
+    T: From<AppointmentView> + Serializable,
+{
+    fn from(value: AppointmentView) -> Self {
+        Self {
+            success: true,
+            message: "Appointment successfully retrieved".to_string(),
+            data: Some(vec![T::from(value)]),
+        }
+    }
+}
+
+impl<T> From<Vec<AppointmentView>> for Response<T>
+where
+    T: From<AppointmentView> + Serializable,
+    Vec<T>: From<Vec<AppointmentView>>,
+{
+    fn from(value: Vec<AppointmentView>) -> Self {
+        Self {
+            success: true,
+            message: "Appointments successfully retrieved".to_string(),
+            data: Some(value.into()),
+        }
+    }
+}
+
+impl<T> From<ShiftRun> for Response<T>
+where
+    T: From<ShiftRun> + Serializable,
+{
+    fn from(value: ShiftRun) -> Self {
+        Self {
+            success: true,
+            message: "Current shift successfully retrieved".to_string(),
+            data: Some(vec![T::from(value)]),
+        }
+    }
+}
+
+impl<T> From<entity::shifts::Model> for Response<T>
+where
+    T: From<entity::shifts::Model> + Serializable,
+{
+    fn from(value: entity::shifts::Model) -> Self {
+        Self {
+            success: true,
+            message: "Shift successfully retrieved".to_string(),
+            data: Some(vec![T::from(value)]),
+        }
+    }
+}
+
+impl<T> From<Vec<entity::shifts::Model>> for Response<T>
+where
+    T: From<entity::shifts::Model> + Serializable,
+    Vec<T>: From<Vec<entity::shifts::Model>>,
+{
+    fn from(value: Vec<entity::shifts::Model>) -> Self {
+        Self {
+            success: true,
+            message: "Shifts successfully retrieved".to_string(),
+            data: Some(value.into()),
+        }
+    }
+}
+
+impl<T> From<entity::webhooks::Model> for Response<T>
+where
+    T: From<entity::webhooks::Model> + Serializable,
+{
+    fn from(value: entity::webhooks::Model) -> Self {
+        Self {
+            success: true,
+            message: "Webhook successfully retrieved".to_string(),
+            data: Some(vec![T::from(value)]),
+        }
+    }
+}
+
+impl<T> From<Vec<entity::webhooks::Model>> for Response<T>
+where
+    T: From<entity::webhooks::Model> + Serializable,
+    Vec<T>: From<Vec<entity::webhooks::Model>>,
+{
+    fn from(value: Vec<entity::webhooks::Model>) -> Self {
+        Self {
+            success: true,
+            message: "Webhooks successfully retrieved".to_string(),
+            data: Some(value.into()),
+        }
+    }
+}
+
+impl<T> From<entity::webhook_deliveries::Model> for Response<T>
+where
+    T: From<entity::webhook_deliveries::Model> + Serializable,
+{
+    fn from(value: entity::webhook_deliveries::Model) -> Self {
+        Self {
+            success: true,
+            message: "Delivery successfully retrieved".to_string(),
+            data: Some(vec![T::from(value)]),
+        }
+    }
+}
+
+impl<T> From<Vec<entity::webhook_deliveries::Model>> for Response<T>
+where
+    T: From<entity::webhook_deliveries::Model> + Serializable,
+    Vec<T>: From<Vec<entity::webhook_deliveries::Model>>,
+{
+    fn from(value: Vec<entity::webhook_deliveries::Model>) -> Self {
+        Self {
+            success: true,
+            message: "Deliveries successfully retrieved".to_string(),
+            data: Some(value.into()),
+        }
+    }
+}
+
+impl<T> From<Vec<AwayResident>> for Response<T>
+where
+    T: From<AwayResident> + Serializable,
+    Vec<T>: From<Vec<AwayResident>>,
+{
+    fn from(value: Vec<AwayResident>) -> Self {
+        Self {
+            success: true,
+            message: "Residents in AWAY successfully retrieved".to_string(),
+            data: Some(value.into()),
+        }
+    }
+}
+
+impl<T> From<Vec<ResidentMatch>> for Response<T>
+where
+    T: From<ResidentMatch> + Serializable,
+    Vec<T>: From<Vec<ResidentMatch>>,
+{
+    fn from(value: Vec<ResidentMatch>) -> Self {
+        Self {
+            success: true,
+            message: "Resident search successfully completed".to_string(),
+            data: Some(value.into()),
+        }
+    }
+}
+
+impl<T> From<Vec<Discrepancy>> for Response<T>
+where
+    T: From<Discrepancy> + Serializable,
+    Vec<T>: From<Vec<Discrepancy>>,
+{
+    fn from(value: Vec<Discrepancy>) -> Self {
+        Self {
+            success: true,
+            message: "Discrepancies successfully retrieved".to_string(),
+            data: Some(value.into()),
+        }
+    }
+}
+
+impl<T> From<Vec<BatchResult>> for Response<T>
+where
+    T: From<BatchResult> + Serializable,
+    Vec<T>: From<Vec<BatchResult>>,
+{
+    fn from(value: Vec<BatchResult>) -> Self {
+        Self {
+            success: true,
+            message: "Batch successfully processed".to_string(),
+            data: Some(value.into()),
+        }
+    }
+}
+
 impl<T> From<entity::timestamps::Model> for Response<T>
 where
     T: From<entity::timestamps::Model> + Serializable,
@@ -194,6 +596,41 @@ where
             data: None,
         }
     }
+    pub fn shift_not_found() -> Self {
+        Self {
+            success: false,
+            message: "Shift not found".to_string(),
+            data: None,
+        }
+    }
+    pub fn note_not_found() -> Self {
+        Self {
+            success: false,
+            message: "Note not found".to_string(),
+            data: None,
+        }
+    }
+    pub fn rule_not_found() -> Self {
+        Self {
+            success: false,
+            message: "Rule not found".to_string(),
+            data: None,
+        }
+    }
+    pub fn alert_not_found() -> Self {
+        Self {
+            success: false,
+            message: "Alert not found".to_string(),
+            data: None,
+        }
+    }
+    pub fn webhook_not_found() -> Self {
+        Self {
+            success: false,
+            message: "Webhook not found".to_string(),
+            data: None,
+        }
+    }
     pub fn resident_not_found() -> Self {
         Self {
             success: false,
@@ -0,0 +1,319 @@
+use super::{
+    alerts,
+    away::{away_residents, AwayParams},
+};
+use chrono::{Local, NaiveDateTime};
+use entity::{
+    alerts::Entity as Alerts,
+    locations::{self, Entity as Locations},
+    residents::{self, Entity as Residents},
+    rules::{self, Entity as Rules},
+    sea_orm_active_enums::RuleKind,
+};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait,
+    QueryFilter,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// who alerts are resolved by when their rule clears on its own
+pub const SYSTEM: &str = "system";
+
+#[derive(Debug, Deserialize)]
+pub struct NewRule {
+    pub name: String,
+    pub kind: RuleKind,
+    pub minutes: Option<i32>,
+    pub location: Option<i32>,
+    pub unit: Option<i32>,
+    pub max_count: Option<i32>,
+    pub resident_level: Option<i32>,
+    pub location_level: Option<i32>,
+}
+
+/// `null` clears an optional field, omitting it leaves it unchanged
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateRule {
+    pub name: Option<String>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub minutes: Option<Option<i32>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub location: Option<Option<i32>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub unit: Option<Option<i32>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub max_count: Option<Option<i32>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub resident_level: Option<Option<i32>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub location_level: Option<Option<i32>>,
+    pub active: Option<bool>,
+}
+
+/// Why a rule definition was turned down
+pub fn validate(rule: &rules::Model) -> Result<(), String> {
+    match rule.kind {
+        RuleKind::AwayTooLong if rule.minutes.is_none_or(|m| m <= 0) => {
+            Err("An away_too_long rule needs minutes greater than 0".to_string())
+        }
+        RuleKind::UnitCount if rule.location.is_none() => {
+            Err("A unit_count rule needs a location".to_string())
+        }
+        RuleKind::UnitCount if rule.max_count.is_none_or(|c| c < 0) => {
+            Err("A unit_count rule needs a max_count of 0 or more".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// A rule's condition holding for one resident or location
+#[derive(Debug)]
+struct Violation {
+    resident_id: Option<i32>,
+    location: Option<i32>,
+    message: String,
+}
+
+impl Violation {
+    fn matches(&self, alert: &entity::alerts::Model) -> bool {
+        alert.resident_id == self.resident_id && alert.location == self.location
+    }
+}
+
+/// Which of a rule's open alerts an evaluation speaks for
+enum Scope {
+    All,
+    Resident(i32),
+}
+
+/// Where the rule's condition holds, for every resident or only `only`
+async fn violations<C: ConnectionTrait>(
+    db: &C,
+    rule: &rules::Model,
+    only: Option<&residents::Model>,
+    now: NaiveDateTime,
+) -> Result<Vec<Violation>, DbErr> {
+    let mut found = Vec::new();
+    let mut query = Residents::find();
+    if let Some(unit) = rule.unit {
+        query = query.filter(residents::Column::Unit.eq(unit));
+    }
+    if let Some(resident) = only {
+        query = query.filter(residents::Column::Id.eq(resident.id));
+    }
+    match rule.kind {
+        RuleKind::AwayTooLong => {
+            let limit = rule.minutes.unwrap_or_default();
+            let params = AwayParams {
+                min_minutes: Some(limit.into()),
+                unit: rule.unit,
+            };
+            for away in away_residents(db, &params, now).await? {
+                if only.is_some_and(|resident| resident.id != away.resident.id) {
+                    continue;
+                }
+                let last_seen = away
+                    .last_location_name
+                    .map_or(String::new(), |name| format!(", last seen in {}", name));
+                found.push(Violation {
+                    resident_id: Some(away.resident.id),
+                    location: Some(0),
+                    message: format!(
+                        "{} has been AWAY for {} minutes, over the {} minute limit{}",
+                        away.resident.name,
+                        away.minutes.unwrap_or_default(),
+                        limit,
+                        last_seen
+                    ),
+                });
+            }
+        }
+        RuleKind::UnitCount => {
+            let Some(location) = rule.location else {
+                return Ok(found);
+            };
+            let count = query
+                .filter(residents::Column::CurrentLocation.eq(location))
+                .count(db)
+                .await?;
+            if count > rule.max_count.unwrap_or_default() as u64 {
+                let name = Locations::find_by_id(location)
+                    .one(db)
+                    .await?
+                    .map_or(location.to_string(), |l| l.name);
+                let from = rule
+                    .unit
+                    .map_or(String::new(), |u| format!(" from unit {}", u));
+                found.push(Violation {
+                    resident_id: None,
+                    location: Some(location),
+                    message: format!(
+                        "{} residents{} in {}, more than {}",
+                        count,
+                        from,
+                        name,
+                        rule.max_count.unwrap_or_default()
+                    ),
+                });
+            }
+        }
+        RuleKind::LevelMismatch => {
+            if let Some(level) = rule.resident_level {
+                query = query.filter(residents::Column::Level.eq(level));
+            }
+            if let Some(location) = rule.location {
+                query = query.filter(residents::Column::CurrentLocation.eq(location));
+            }
+            let placed = query
+                .filter(residents::Column::CurrentLocation.ne(0))
+                .all(db)
+                .await?;
+            let levels: HashMap<i32, locations::Model> = Locations::find()
+                .all(db)
+                .await?
+                .into_iter()
+                .map(|location| (location.id, location))
+                .collect();
+            for resident in placed {
+                let Some(location) = levels.get(&resident.current_location) else {
+                    continue;
+                };
+                if rule
+                    .location_level
+                    .is_some_and(|level| level != location.level)
+                {
+                    continue;
+                }
+                if resident.level < location.level {
+                    found.push(Violation {
+                        resident_id: Some(resident.id),
+                        location: Some(location.id),
+                        message: format!(
+                            "level {} resident {} is in {}, a level {} location",
+                            resident.level, resident.name, location.name, location.level
+                        ),
+                    });
+                }
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// Raise an alert for each violation that doesn't have an open one yet and
+/// resolve the open alerts in `scope` whose condition has cleared. Returns the
+/// alerts raised.
+async fn reconcile<C: ConnectionTrait>(
+    db: &C,
+    rule: &rules::Model,
+    found: Vec<Violation>,
+    scope: Scope,
+) -> Result<Vec<entity::alerts::Model>, DbErr> {
+    let mut open = Alerts::find()
+        .filter(entity::alerts::Column::RuleId.eq(rule.id))
+        .filter(entity::alerts::Column::ResolvedAt.is_null());
+    if let Scope::Resident(id) = scope {
+        open = open.filter(entity::alerts::Column::ResidentId.eq(id));
+    }
+    let open = open.all(db).await?;
+    for alert in &open {
+        if !found.iter().any(|v| v.matches(alert)) {
+            alerts::resolve(db, alert.clone(), SYSTEM).await?;
+        }
+    }
+    let mut raised = Vec::new();
+    for violation in found {
+        if !open.iter().any(|alert| violation.matches(alert)) {
+            raised.push(
+                alerts::raise_for_rule(
+                    db,
+                    rule,
+                    violation.resident_id,
+                    violation.location,
+                    violation.message,
+                )
+                .await?,
+            );
+        }
+    }
+    Ok(raised)
+}
+
+/// Evaluate the rules a movement can change: counts at the location the
+/// resident left or arrived at, their level at the new location, and clearing
+/// an AWAY alert when they come back. Runs inside the scan's transaction and
+/// returns the alerts raised, which the kiosk shows with the movement.
+pub async fn on_movement<C: ConnectionTrait>(
+    db: &C,
+    resident: &residents::Model,
+    previous_location: i32,
+    now: NaiveDateTime,
+) -> Result<Vec<entity::alerts::Model>, DbErr> {
+    let rules = Rules::find()
+        .filter(rules::Column::Active.eq(true))
+        .all(db)
+        .await?;
+    let mut raised = Vec::new();
+    for rule in rules {
+        match rule.kind {
+            // going away can't be overdue yet, coming back clears it
+            RuleKind::AwayTooLong => {
+                if resident.current_location != 0 {
+                    reconcile(db, &rule, Vec::new(), Scope::Resident(resident.id)).await?;
+                }
+            }
+            RuleKind::UnitCount => {
+                if rule.location == Some(resident.current_location)
+                    || rule.location == Some(previous_location)
+                {
+                    let found = violations(db, &rule, None, now).await?;
+                    raised.extend(reconcile(db, &rule, found, Scope::All).await?);
+                }
+            }
+            RuleKind::LevelMismatch => {
+                let found = violations(db, &rule, Some(resident), now).await?;
+                raised.extend(reconcile(db, &rule, found, Scope::Resident(resident.id)).await?);
+            }
+        }
+    }
+    Ok(raised)
+}
+
+/// Evaluate every active rule against the whole facility
+pub async fn sweep<C: ConnectionTrait>(db: &C, now: NaiveDateTime) -> Result<usize, DbErr> {
+    let rules = Rules::find()
+        .filter(rules::Column::Active.eq(true))
+        .all(db)
+        .await?;
+    let mut raised = 0;
+    for rule in rules {
+        let found = violations(db, &rule, None, now).await?;
+        raised += reconcile(db, &rule, found, Scope::All).await?.len();
+    }
+    Ok(raised)
+}
+
+/// Periodic sweep, catches what no scan triggers, like time spent in AWAY
+pub async fn run(db: DatabaseConnection, every: std::time::Duration) {
+    loop {
+        actix_web::rt::time::sleep(every).await;
+        if let Err(err) = sweep(&db, Local::now().naive_local()).await {
+            log::error!("rules sweep: {}", err);
+        }
+    }
+}
+
+/// RULES_SWEEP_SECONDS, a minute when unset
+pub fn sweep_interval() -> std::time::Duration {
+    crate::app_config::env_seconds("RULES_SWEEP_SECONDS", 60)
+}
+
+/// Whether the location a rule points at exists
+pub async fn location_exists<C: ConnectionTrait>(db: &C, location: i32) -> Result<bool, DbErr> {
+    Ok(Locations::find()
+        .filter(locations::Column::Id.eq(location))
+        .count(db)
+        .await?
+        > 0)
+}
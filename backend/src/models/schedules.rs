@@ -0,0 +1,111 @@
+use super::alerts::{Breach, CLOSED_LOCATION};
+use chrono::{Datelike, NaiveDateTime, NaiveTime};
+use entity::{
+    location_schedules::{self, Entity as LocationSchedules},
+    locations::Entity as Locations,
+    residents,
+};
+use sea_orm::{ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct NewSchedule {
+    /// Monday = 0 through Sunday = 6
+    pub weekday: i32,
+    pub opens_at: NaiveTime,
+    pub closes_at: NaiveTime,
+    #[serde(default)]
+    pub allowed_levels: Vec<i32>,
+    #[serde(default)]
+    pub allowed_units: Vec<i32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateSchedule {
+    pub weekday: Option<i32>,
+    pub opens_at: Option<NaiveTime>,
+    pub closes_at: Option<NaiveTime>,
+    pub allowed_levels: Option<Vec<i32>>,
+    pub allowed_units: Option<Vec<i32>>,
+}
+
+pub fn valid_weekday(weekday: i32) -> bool {
+    (0..7).contains(&weekday)
+}
+
+/// A window that opens when it closes would never cover any time
+pub fn valid_hours(opens_at: NaiveTime, closes_at: NaiveTime) -> bool {
+    opens_at != closes_at
+}
+
+/// Whether `schedule` has the location open at `at`, including the early
+/// morning tail of a schedule that started the previous day
+pub fn covers(schedule: &location_schedules::Model, at: NaiveDateTime) -> bool {
+    let day = at.weekday().num_days_from_monday() as i32;
+    let time = at.time();
+    if schedule.opens_at <= schedule.closes_at {
+        schedule.weekday == day && schedule.opens_at <= time && time < schedule.closes_at
+    } else {
+        (schedule.weekday == day && time >= schedule.opens_at)
+            || ((schedule.weekday + 1) % 7 == day && time < schedule.closes_at)
+    }
+}
+
+pub fn active_schedule(
+    schedules: &[location_schedules::Model],
+    at: NaiveDateTime,
+) -> Option<&location_schedules::Model> {
+    schedules.iter().find(|schedule| covers(schedule, at))
+}
+
+fn admits(schedule: &location_schedules::Model, resident: &residents::Model) -> bool {
+    (schedule.allowed_levels.is_empty() || schedule.allowed_levels.contains(&resident.level))
+        && (schedule.allowed_units.is_empty() || schedule.allowed_units.contains(&resident.unit))
+}
+
+/// Check `resident` arriving at `location` at `at` against the location's
+/// schedules. Locations without any schedule are always open.
+pub async fn check_schedule<C: ConnectionTrait>(
+    db: &C,
+    resident: &residents::Model,
+    location: i32,
+    at: NaiveDateTime,
+) -> Result<Option<Breach>, DbErr> {
+    let schedules = LocationSchedules::find()
+        .filter(location_schedules::Column::LocationId.eq(location))
+        .all(db)
+        .await?;
+    if schedules.is_empty() {
+        return Ok(None);
+    }
+    let open: Vec<&location_schedules::Model> = schedules
+        .iter()
+        .filter(|schedule| covers(schedule, at))
+        .collect();
+    let problem = if open.is_empty() {
+        "outside scheduled hours"
+    } else if !open.iter().any(|schedule| admits(schedule, resident)) {
+        "while it is not open to their level or unit"
+    } else {
+        return Ok(None);
+    };
+    let Some(loc) = Locations::find_by_id(location).one(db).await? else {
+        return Ok(None);
+    };
+    let action = if loc.refuse_when_closed {
+        "refused entry to"
+    } else {
+        "arrived at"
+    };
+    let message = format!(
+        "Closed location: {} ({}) {} {} {}",
+        resident.name, resident.doc, action, loc.name, problem
+    );
+    Ok(Some(Breach {
+        refuse: loc.refuse_when_closed,
+        kind: CLOSED_LOCATION,
+        resident_id: resident.id,
+        location,
+        message,
+    }))
+}
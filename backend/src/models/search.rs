@@ -0,0 +1,143 @@
+use entity::residents::{self, Entity as Residents};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait,
+    FromQueryResult, QueryFilter, Statement, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// How close a name has to be to the query, as pg_trgm word similarity
+const NAME_THRESHOLD: f64 = 0.3;
+const DEFAULT_LIMIT: u64 = 20;
+const MAX_LIMIT: u64 = 100;
+
+// Each resident is scored on every field and keeps their best one. Names are
+// compared by trigrams, which pg_trgm takes word by word, so "Doe, John" finds
+// "John Doe" as well as "John". The WHERE clause only uses conditions the
+// trigram indexes from the resident_search migration can answer.
+const SEARCH: &str = r#"
+SELECT r.id, best.matched, best.score::float8 AS score
+FROM residents r
+CROSS JOIN LATERAL (
+    SELECT matched, score FROM (VALUES
+        ('rfid', CASE WHEN r.rfid = $2 THEN 1.0 WHEN r.rfid LIKE $3 THEN 0.9 ELSE 0 END),
+        ('doc', CASE WHEN r.doc = $1 THEN 1.0 WHEN r.doc LIKE $4 THEN 0.8 ELSE 0 END),
+        ('room', CASE WHEN lower(r.room) = lower($1) THEN 0.85 WHEN r.room ILIKE $4 THEN 0.6 ELSE 0 END),
+        ('name', word_similarity($1, r.name))
+    ) AS fields(matched, score)
+    ORDER BY score DESC
+    LIMIT 1
+) best
+WHERE r.rfid = $2 OR r.rfid LIKE $3 OR r.doc LIKE $4 OR r.room ILIKE $4 OR $1 <% r.name
+ORDER BY best.score DESC, similarity($1, r.name) DESC, r.name
+LIMIT $5"#;
+
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    pub q: String,
+    pub limit: Option<u64>,
+}
+
+/// Which of a resident's fields the query matched best
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchedOn {
+    Rfid,
+    Doc,
+    Room,
+    Name,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResidentMatch {
+    pub resident: residents::Model,
+    pub matched: MatchedOn,
+    /// 1.0 for an exact badge or DOC number, lower the looser the match
+    pub score: f64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct Hit {
+    id: i32,
+    matched: String,
+    score: f64,
+}
+
+/// `raw` with LIKE's wildcards escaped, followed by one, for a prefix match
+fn prefix(raw: &str) -> String {
+    let mut pattern = String::with_capacity(raw.len() + 1);
+    for c in raw.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+/// Residents matching `q` by badge (exact in any format the readers send, or a
+/// prefix of the digits), DOC number, room or name, best match first
+pub async fn search(
+    db: &DatabaseConnection,
+    params: &SearchParams,
+) -> Result<Vec<ResidentMatch>, DbErr> {
+    let q = params.q.trim();
+    if q.is_empty() {
+        return Ok(Vec::new());
+    }
+    let badge = super::badges::canonical(q).ok();
+    // badges are stored without leading zeros
+    let badge_prefix = (q.chars().all(|c| c.is_ascii_digit()))
+        .then(|| q.trim_start_matches('0'))
+        .filter(|digits| !digits.is_empty())
+        .map(prefix);
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    // `<%` takes its cut-off from a setting; SET LOCAL keeps ours to this transaction
+    let txn = db.begin().await?;
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "SELECT set_config('pg_trgm.word_similarity_threshold', $1, true)",
+        [NAME_THRESHOLD.to_string().into()],
+    ))
+    .await?;
+    let hits = Hit::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        SEARCH,
+        [
+            q.into(),
+            badge.into(),
+            badge_prefix.into(),
+            prefix(q).into(),
+            (limit as i64).into(),
+        ],
+    ))
+    .all(&txn)
+    .await?;
+    let mut residents: HashMap<i32, residents::Model> = Residents::find()
+        .filter(residents::Column::Id.is_in(hits.iter().map(|hit| hit.id)))
+        .all(&txn)
+        .await?
+        .into_iter()
+        .map(|resident| (resident.id, resident))
+        .collect();
+    txn.commit().await?;
+
+    Ok(hits
+        .into_iter()
+        .filter_map(|hit| {
+            let matched = match hit.matched.as_str() {
+                "rfid" => MatchedOn::Rfid,
+                "doc" => MatchedOn::Doc,
+                "room" => MatchedOn::Room,
+                _ => MatchedOn::Name,
+            };
+            Some(ResidentMatch {
+                resident: residents.remove(&hit.id)?,
+                matched,
+                score: hit.score,
+            })
+        })
+        .collect())
+}
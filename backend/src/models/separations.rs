@@ -0,0 +1,127 @@
+use super::alerts::{self, SEPARATION};
+use chrono::NaiveDateTime;
+use entity::{
+    locations::Entity as Locations,
+    residents::{self, Entity as Residents},
+    separations::{self, Entity as Separations},
+};
+use sea_orm::{ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Deserialize)]
+pub struct NewSeparation {
+    pub resident: String,
+    pub separated: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSeparation {
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SeparationParams {
+    pub rfid: Option<String>,
+}
+
+/// A separation with both residents identified by RFID and name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeparationView {
+    pub id: i32,
+    pub resident: String,
+    pub resident_name: String,
+    pub separated: String,
+    pub separated_name: String,
+    pub reason: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl SeparationView {
+    pub fn new(sep: separations::Model, residents: &HashMap<i32, residents::Model>) -> Self {
+        let (resident, resident_name) = rfid_and_name(residents.get(&sep.resident_id));
+        let (separated, separated_name) = rfid_and_name(residents.get(&sep.separated_id));
+        Self {
+            id: sep.id,
+            resident,
+            resident_name,
+            separated,
+            separated_name,
+            reason: sep.reason,
+            created_at: sep.created_at,
+        }
+    }
+}
+
+fn rfid_and_name(resident: Option<&residents::Model>) -> (String, String) {
+    resident
+        .map(|r| (r.rfid.clone(), r.name.clone()))
+        .unwrap_or_default()
+}
+
+/// Separations are stored once per pair with the lower resident id first
+pub fn ordered_pair(a: i32, b: i32) -> (i32, i32) {
+    (a.min(b), a.max(b))
+}
+
+/// Called after a resident arrives somewhere: raise a conflict alert for every
+/// separated resident whose `current_location` is that same location.
+pub async fn check_separations<C: ConnectionTrait>(
+    db: &C,
+    resident: &residents::Model,
+) -> Result<Vec<entity::alerts::Model>, DbErr> {
+    if resident.current_location == 0 {
+        return Ok(Vec::new());
+    }
+    let seps = Separations::find()
+        .filter(
+            Condition::any()
+                .add(separations::Column::ResidentId.eq(resident.id))
+                .add(separations::Column::SeparatedId.eq(resident.id)),
+        )
+        .all(db)
+        .await?;
+    if seps.is_empty() {
+        return Ok(Vec::new());
+    }
+    let reasons: HashMap<i32, String> = seps
+        .into_iter()
+        .map(|sep| {
+            let other = if sep.resident_id == resident.id {
+                sep.separated_id
+            } else {
+                sep.resident_id
+            };
+            (other, sep.reason)
+        })
+        .collect();
+    let present = Residents::find()
+        .filter(residents::Column::Id.is_in(reasons.keys().copied()))
+        .filter(residents::Column::CurrentLocation.eq(resident.current_location))
+        .all(db)
+        .await?;
+    let location = Locations::find_by_id(resident.current_location)
+        .one(db)
+        .await?
+        .map(|loc| loc.name)
+        .unwrap_or_else(|| resident.current_location.to_string());
+    let mut raised = Vec::new();
+    for other in present {
+        let message = format!(
+            "Keep-separate conflict: {} ({}) arrived at {} where {} ({}) is present. Reason: {}",
+            resident.name, resident.doc, location, other.name, other.doc, reasons[&other.id]
+        );
+        raised.push(
+            alerts::raise(
+                db,
+                SEPARATION,
+                Some(resident.id),
+                Some(resident.current_location),
+                message,
+            )
+            .await?,
+        );
+    }
+    Ok(raised)
+}
@@ -0,0 +1,148 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use entity::{
+    shifts::{self, Entity as Shifts},
+    timestamps,
+};
+use sea_orm::{
+    sea_query::{Alias, Expr, Func, SimpleExpr},
+    ConnectionTrait, DbErr, EntityTrait, QueryOrder,
+};
+use serde::{Deserialize, Serialize};
+
+/// `?shift=` on the timestamp endpoints: a shift name, or `current`
+#[derive(Debug, Deserialize)]
+pub struct ShiftParams {
+    pub shift: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewShift {
+    pub name: String,
+    pub starts_at: NaiveTime,
+    pub ends_at: NaiveTime,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateShift {
+    pub name: Option<String>,
+    pub starts_at: Option<NaiveTime>,
+    pub ends_at: Option<NaiveTime>,
+}
+
+/// One run of a shift, from its start to its end
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShiftRun {
+    #[serde(flatten)]
+    pub shift: shifts::Model,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+pub fn wraps_midnight(shift: &shifts::Model) -> bool {
+    shift.ends_at <= shift.starts_at
+}
+
+/// The run of `shift` that begins on `date`
+pub fn run_on(shift: &shifts::Model, date: NaiveDate) -> ShiftRun {
+    let end_date = if wraps_midnight(shift) {
+        date + Duration::days(1)
+    } else {
+        date
+    };
+    ShiftRun {
+        shift: shift.clone(),
+        start: date.and_time(shift.starts_at),
+        end: end_date.and_time(shift.ends_at),
+    }
+}
+
+/// The most recent run of `shift` to have started by `at`
+pub fn latest_run(shift: &shifts::Model, at: NaiveDateTime) -> ShiftRun {
+    let today = run_on(shift, at.date());
+    if today.start <= at {
+        today
+    } else {
+        run_on(shift, at.date() - Duration::days(1))
+    }
+}
+
+pub async fn all_shifts<C: ConnectionTrait>(db: &C) -> Result<Vec<shifts::Model>, DbErr> {
+    Shifts::find()
+        .order_by_asc(shifts::Column::StartsAt)
+        .all(db)
+        .await
+}
+
+pub async fn find_shift<C: ConnectionTrait>(
+    db: &C,
+    name: &str,
+) -> Result<Option<shifts::Model>, DbErr> {
+    Ok(all_shifts(db)
+        .await?
+        .into_iter()
+        .find(|shift| shift.name.eq_ignore_ascii_case(name)))
+}
+
+/// The shift in progress at `at`
+pub async fn current_shift<C: ConnectionTrait>(
+    db: &C,
+    at: NaiveDateTime,
+) -> Result<Option<ShiftRun>, DbErr> {
+    Ok(all_shifts(db)
+        .await?
+        .iter()
+        .map(|shift| latest_run(shift, at))
+        .find(|run| at < run.end))
+}
+
+/// Resolve `?shift=`: `current` is the shift in progress, anything else a
+/// shift name. Ok(None) means no such shift.
+pub async fn resolve_shift<C: ConnectionTrait>(
+    db: &C,
+    name: &str,
+    now: NaiveDateTime,
+) -> Result<Option<shifts::Model>, DbErr> {
+    if name.eq_ignore_ascii_case("current") {
+        Ok(current_shift(db, now).await?.map(|run| run.shift))
+    } else {
+        find_shift(db, name).await
+    }
+}
+
+/// The period the "today" timestamp endpoints cover: the latest run of the
+/// requested shift, otherwise the current shift, or the last 24 hours if no
+/// shift covers `now`. Ok(None) means the requested shift does not exist.
+pub async fn shift_window<C: ConnectionTrait>(
+    db: &C,
+    shift: Option<&str>,
+    now: NaiveDateTime,
+) -> Result<Option<(NaiveDateTime, NaiveDateTime)>, DbErr> {
+    match shift {
+        Some(name) => Ok(resolve_shift(db, name, now)
+            .await?
+            .map(|shift| latest_run(&shift, now))
+            .map(|run| (run.start, run.end))),
+        None => Ok(Some(match current_shift(db, now).await? {
+            Some(run) => (run.start, run.end),
+            None => (now - Duration::days(1), now),
+        })),
+    }
+}
+
+/// Restrict timestamps to the hours of `shift` on any day, for the date
+/// range endpoints
+pub fn during_shift(shift: &shifts::Model) -> SimpleExpr {
+    let time_of_day = || {
+        Expr::expr(Func::cast_as(
+            Expr::col((timestamps::Entity, timestamps::Column::Ts)),
+            Alias::new("time"),
+        ))
+    };
+    let after_start = time_of_day().gte(shift.starts_at);
+    let before_end = time_of_day().lt(shift.ends_at);
+    if wraps_midnight(shift) {
+        after_start.or(before_end)
+    } else {
+        after_start.and(before_end)
+    }
+}
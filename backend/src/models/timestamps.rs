@@ -1,7 +1,10 @@
 use std::fmt::{Display, Formatter};
 
-use chrono::NaiveDate;
-use entity::residents;
+use super::movements::ScanOutcome;
+use super::shifts::during_shift;
+use chrono::{NaiveDate, NaiveDateTime};
+use entity::{residents, shifts, timestamps};
+use sea_orm::{ColumnTrait, DbErr, EntityTrait, QueryFilter, Select, Value};
 use serde::Deserializer;
 use serde::{Deserialize, Serialize};
 
@@ -17,6 +20,20 @@ pub struct RangeParams {
 pub struct ResidentTimestamp {
     pub resident: entity::residents::Model,
     pub timestamp: entity::timestamps::Model,
+    /// conflicts raised by this movement, shown on the kiosk
+    #[serde(default)]
+    pub alerts: Vec<entity::alerts::Model>,
+    /// the resident's active high severity notes, shown on the kiosk
+    #[serde(default)]
+    pub flags: Vec<entity::resident_notes::Model>,
+}
+
+/// A scan that was turned away, with what the kiosk shows all the same
+#[derive(Debug, Eq, PartialEq, Clone, Deserialize, Serialize)]
+pub struct Refusal {
+    pub resident: entity::residents::Model,
+    /// the resident's active high severity notes, shown on the kiosk
+    pub flags: Vec<entity::resident_notes::Model>,
 }
 
 impl ResidentTimestamp {
@@ -24,10 +41,34 @@ impl ResidentTimestamp {
         Self {
             resident,
             timestamp,
+            alerts: Vec::new(),
+            flags: Vec::new(),
         }
     }
 }
 
+/// The query behind the date range endpoints: timestamps from `start` to
+/// `end` inclusive, only those in `shift`'s hours when one is given
+pub fn timestamps_between<V: Into<Value>>(
+    start: V,
+    end: V,
+    shift: Option<&shifts::Model>,
+) -> Select<timestamps::Entity> {
+    let query = timestamps::Entity::find().filter(timestamps::Column::Ts.between(start, end));
+    match shift {
+        Some(shift) => query.filter(during_shift(shift)),
+        None => query,
+    }
+}
+
+/// The query behind the shift endpoints and the movement log: timestamps at
+/// or after `start` and before `end`, so back to back windows don't share a scan
+pub fn timestamps_within(start: NaiveDateTime, end: NaiveDateTime) -> Select<timestamps::Entity> {
+    timestamps::Entity::find()
+        .filter(timestamps::Column::Ts.gte(start))
+        .filter(timestamps::Column::Ts.lt(end))
+}
+
 // Deserialize date strings into NaiveDate
 fn deserialize_date<'de, D>(deserializer: D) -> Result<NaiveDate, D::Error>
 where
@@ -47,4 +88,89 @@ impl Display for PostTimestamp {
 pub struct PostTimestamp {
     pub rfid: String,
     pub location: i32,
+    /// which reader sent the scan, debouncing is per scanner when given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scanner: Option<String>,
+    /// when the badge was read by the client, defaults to when the server gets it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scanned_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Recorded,
+    Ignored,
+    Backfilled,
+    Refused,
+    InvalidBadge,
+    UnknownResident,
+    /// the database errored, nothing was recorded for this scan
+    Failed,
+}
+
+/// What happened to the scan at `index` of the uploaded batch
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BatchResult {
+    pub index: usize,
+    pub rfid: String,
+    pub status: BatchStatus,
+    pub message: String,
+    pub movement: Option<ResidentTimestamp>,
+    /// the resident's flags when the scan was refused, a movement carries its own
+    #[serde(default)]
+    pub flags: Vec<entity::resident_notes::Model>,
+}
+
+impl BatchResult {
+    pub fn new(index: usize, rfid: String, outcome: ScanOutcome) -> Self {
+        let mut flags = Vec::new();
+        let (status, message, movement) = match outcome {
+            ScanOutcome::Recorded(movement) => (
+                BatchStatus::Recorded,
+                String::from("Movement recorded"),
+                Some(movement),
+            ),
+            ScanOutcome::Ignored(movement) => (
+                BatchStatus::Ignored,
+                String::from("Duplicate scan ignored"),
+                Some(movement),
+            ),
+            ScanOutcome::Backfilled(movement) => (
+                BatchStatus::Backfilled,
+                String::from("Added to history, a newer movement is already recorded"),
+                Some(movement),
+            ),
+            ScanOutcome::Refused(message, refusal) => {
+                flags = refusal.flags;
+                (BatchStatus::Refused, message, None)
+            }
+            ScanOutcome::InvalidBadge(message) => (BatchStatus::InvalidBadge, message, None),
+            ScanOutcome::UnknownResident => (
+                BatchStatus::UnknownResident,
+                String::from("Not found in system, please add Resident."),
+                None,
+            ),
+        };
+        Self {
+            index,
+            rfid,
+            status,
+            message,
+            movement,
+            flags,
+        }
+    }
+
+    /// A scan that hit `err` and was rolled back, the batch carries on without it
+    pub fn failed(index: usize, rfid: String, err: &DbErr) -> Self {
+        Self {
+            index,
+            rfid,
+            status: BatchStatus::Failed,
+            message: format!("Scan could not be recorded: {}", err),
+            movement: None,
+            flags: Vec::new(),
+        }
+    }
 }
@@ -0,0 +1,281 @@
+use chrono::{Duration, Local, NaiveDateTime};
+use entity::{
+    sea_orm_active_enums::DeliveryStatus,
+    webhook_deliveries::{self, Entity as WebhookDeliveries},
+    webhooks::{self, Entity as Webhooks},
+};
+use hmac::{Hmac, Mac};
+use sea_orm::{
+    sea_query::{Expr, LockBehavior, LockType},
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    IntoActiveModel, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+
+pub const MOVEMENT: &str = "movement";
+pub const AWAY: &str = "away";
+pub const OVER_CAPACITY: &str = "over_capacity";
+pub const OVERDUE_PASS: &str = "overdue_pass";
+pub const RESIDENT_CREATED: &str = "resident_created";
+pub const RESIDENT_RELEASED: &str = "resident_released";
+
+pub const EVENTS: [&str; 6] = [
+    MOVEMENT,
+    AWAY,
+    OVER_CAPACITY,
+    OVERDUE_PASS,
+    RESIDENT_CREATED,
+    RESIDENT_RELEASED,
+];
+
+pub const EVENT_HEADER: &str = "X-Mvscan-Event";
+pub const DELIVERY_HEADER: &str = "X-Mvscan-Delivery";
+pub const SIGNATURE_HEADER: &str = "X-Mvscan-Signature";
+
+/// attempts before a delivery is given up on and marked failed
+pub const MAX_ATTEMPTS: i32 = 8;
+const BACKOFF_BASE_SECONDS: i64 = 10;
+const BACKOFF_MAX_SECONDS: i64 = 3600;
+const BATCH: u64 = 20;
+/// how long one POST to an endpoint may take
+const SEND_TIMEOUT_SECONDS: u64 = 10;
+/// how long the worker holds a claimed batch before another may take it, the
+/// batch is sent one delivery at a time so this outlasts every send timing out
+const LEASE_SECONDS: i64 = (BATCH * SEND_TIMEOUT_SECONDS) as i64 + 60;
+
+#[derive(Debug, Deserialize)]
+pub struct NewWebhook {
+    pub url: String,
+    pub events: Vec<String>,
+    pub secret: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateWebhook {
+    pub url: Option<String>,
+    pub events: Option<Vec<String>>,
+    pub secret: Option<String>,
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeliveryParams {
+    pub status: Option<DeliveryStatus>,
+    pub page: Option<u64>,
+}
+
+/// Why a webhook definition was turned down
+pub fn validate(url: Option<&str>, events: Option<&[String]>) -> Result<(), String> {
+    if let Some(url) = url {
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            return Err(format!("Invalid webhook url: {}", url));
+        }
+    }
+    if let Some(events) = events {
+        if events.is_empty() {
+            return Err("A webhook needs at least one event".to_string());
+        }
+        if let Some(event) = events.iter().find(|e| !EVENTS.contains(&e.as_str())) {
+            return Err(format!(
+                "Unknown event: {}, expected one of {}",
+                event,
+                EVENTS.join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Queue `event` for every active webhook subscribed to it. Runs on the
+/// caller's connection, so the deliveries only exist if its transaction commits.
+pub async fn enqueue<C: ConnectionTrait, T: Serialize>(
+    db: &C,
+    event: &str,
+    data: &T,
+) -> Result<(), DbErr> {
+    let hooks = Webhooks::find()
+        .filter(webhooks::Column::Active.eq(true))
+        .filter(Expr::cust_with_values("$1 = ANY(events)", [event]))
+        .all(db)
+        .await?;
+    if hooks.is_empty() {
+        return Ok(());
+    }
+    let now = Local::now().naive_local();
+    let payload = json!({
+        "event": event,
+        "occurred_at": now,
+        "data": serde_json::to_value(data).map_err(|e| DbErr::Custom(e.to_string()))?,
+    });
+    let deliveries = hooks.iter().map(|hook| webhook_deliveries::ActiveModel {
+        webhook_id: Set(hook.id),
+        event: Set(event.to_string()),
+        payload: Set(payload.clone()),
+        status: Set(DeliveryStatus::Pending),
+        attempts: Set(0),
+        next_attempt_at: Set(now),
+        created_at: Set(now),
+        ..Default::default()
+    });
+    WebhookDeliveries::insert_many(deliveries).exec(db).await?;
+    Ok(())
+}
+
+/// `sha256=` and the hex HMAC-SHA256 of the body, keyed with the webhook's secret
+pub fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Wait before the attempt after `attempts` failed ones: doubling from ten
+/// seconds, up to an hour
+pub fn backoff(attempts: i32) -> Duration {
+    let exponent = (attempts - 1).clamp(0, 20) as u32;
+    Duration::seconds((BACKOFF_BASE_SECONDS << exponent).min(BACKOFF_MAX_SECONDS))
+}
+
+/// Take the deliveries that are due, pushing them back by a lease so a second
+/// worker skips them while they are in flight
+async fn claim_due(
+    db: &DatabaseConnection,
+    now: NaiveDateTime,
+) -> Result<Vec<webhook_deliveries::Model>, DbErr> {
+    let txn = db.begin().await?;
+    let due = WebhookDeliveries::find()
+        .filter(webhook_deliveries::Column::Status.eq(DeliveryStatus::Pending))
+        .filter(webhook_deliveries::Column::NextAttemptAt.lte(now))
+        .order_by_asc(webhook_deliveries::Column::NextAttemptAt)
+        .order_by_asc(webhook_deliveries::Column::Id)
+        .limit(BATCH)
+        .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+        .all(&txn)
+        .await?;
+    if !due.is_empty() {
+        WebhookDeliveries::update_many()
+            .col_expr(
+                webhook_deliveries::Column::NextAttemptAt,
+                Expr::value(now + Duration::seconds(LEASE_SECONDS)),
+            )
+            .filter(webhook_deliveries::Column::Id.is_in(due.iter().map(|d| d.id)))
+            .exec(&txn)
+            .await?;
+    }
+    txn.commit().await?;
+    Ok(due)
+}
+
+/// POST one delivery to its webhook and record how it went
+async fn attempt(
+    db: &DatabaseConnection,
+    client: &reqwest::Client,
+    delivery: webhook_deliveries::Model,
+) -> Result<(), DbErr> {
+    let Some(hook) = Webhooks::find_by_id(delivery.webhook_id).one(db).await? else {
+        return Ok(());
+    };
+    if !hook.active {
+        // switched off after the event was queued, so it isn't sent at all
+        let mut delivery = delivery.into_active_model();
+        delivery.status = Set(DeliveryStatus::Failed);
+        delivery.error = Set(Some("webhook is inactive".to_string()));
+        delivery.update(db).await?;
+        return Ok(());
+    }
+    let body = delivery.payload.to_string();
+    let result = client
+        .post(&hook.url)
+        .header("Content-Type", "application/json")
+        .header(EVENT_HEADER, &delivery.event)
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .header(SIGNATURE_HEADER, signature(&hook.secret, body.as_bytes()))
+        .body(body)
+        .send()
+        .await;
+    let now = Local::now().naive_local();
+    let attempts = delivery.attempts + 1;
+    let mut delivery = delivery.into_active_model();
+    delivery.attempts = Set(attempts);
+    let error = match result {
+        Ok(response) => {
+            delivery.response_status = Set(Some(response.status().as_u16().into()));
+            if response.status().is_success() {
+                None
+            } else {
+                Some(format!("endpoint answered {}", response.status()))
+            }
+        }
+        Err(err) => {
+            delivery.response_status = Set(None);
+            Some(err.to_string())
+        }
+    };
+    match error {
+        None => {
+            delivery.status = Set(DeliveryStatus::Delivered);
+            delivery.delivered_at = Set(Some(now));
+            delivery.error = Set(None);
+        }
+        Some(error) => {
+            log::warn!("webhook {} delivery failed: {}", hook.url, error);
+            if attempts >= MAX_ATTEMPTS {
+                delivery.status = Set(DeliveryStatus::Failed);
+            } else {
+                delivery.next_attempt_at = Set(now + backoff(attempts));
+            }
+            delivery.error = Set(Some(error));
+        }
+    }
+    delivery.update(db).await?;
+    Ok(())
+}
+
+/// Send whatever is due, returns how many deliveries were attempted
+pub async fn deliver_due(
+    db: &DatabaseConnection,
+    client: &reqwest::Client,
+) -> Result<usize, DbErr> {
+    let due = claim_due(db, Local::now().naive_local()).await?;
+    let count = due.len();
+    for delivery in due {
+        attempt(db, client, delivery).await?;
+    }
+    Ok(count)
+}
+
+/// Delivery worker, polls for due deliveries every `poll` until the server stops
+pub async fn run(db: DatabaseConnection, poll: std::time::Duration) {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(SEND_TIMEOUT_SECONDS))
+        .build()
+        .expect("failed to build webhook client");
+    loop {
+        match deliver_due(&db, &client).await {
+            // keep going while there is a backlog
+            Ok(count) if count as u64 == BATCH => continue,
+            Ok(_) => {}
+            Err(err) => log::error!("webhook worker: {}", err),
+        }
+        actix_web::rt::time::sleep(poll).await;
+    }
+}
+
+/// WEBHOOK_POLL_SECONDS, one second when unset
+pub fn poll_interval() -> std::time::Duration {
+    crate::app_config::env_seconds("WEBHOOK_POLL_SECONDS", 1)
+}
+
+/// Queue a failed delivery to be sent again straight away
+pub async fn retry<C: ConnectionTrait>(
+    db: &C,
+    delivery: webhook_deliveries::Model,
+) -> Result<webhook_deliveries::Model, DbErr> {
+    let mut delivery = delivery.into_active_model();
+    delivery.status = Set(DeliveryStatus::Pending);
+    delivery.attempts = Set(0);
+    delivery.next_attempt_at = Set(Local::now().naive_local());
+    delivery.update(db).await
+}
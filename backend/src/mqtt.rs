@@ -0,0 +1,283 @@
+//! Optional MQTT ingestion: scanners publish badges to a topic instead of
+//! calling the HTTP API, and get the same response back as an ack.
+
+use crate::models::{
+    movements::{apply_scan, Debouncer, ScanClock},
+    response::Response,
+    timestamps::PostTimestamp,
+};
+use chrono::NaiveDateTime;
+use rumqttc::{AsyncClient, Event, EventLoop, MqttOptions, Packet, Publish, QoS};
+use sea_orm::{DatabaseConnection, DbErr};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{
+    mpsc::{self, Sender},
+    OwnedSemaphorePermit, Semaphore,
+};
+
+/// Scans taken off the broker and not yet acked, across all devices. Past
+/// this the event loop stops reading, so the broker holds on to the rest.
+const MAX_IN_FLIGHT: usize = 64;
+/// Scans one device can have waiting for its worker
+const DEVICE_QUEUE: usize = 16;
+/// First wait before a scan the database failed on is tried again, doubling
+/// up to `RETRY_MAX`
+const RETRY_BASE: Duration = Duration::from_millis(500);
+const RETRY_MAX: Duration = Duration::from_secs(30);
+
+/// Read from the environment, ingestion is off unless MQTT_URL is set
+#[derive(Debug, Clone)]
+pub struct MqttConfig {
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    /// subscription, the `+` level names the device, e.g. `scanners/+/scan`
+    pub topic: String,
+    /// where acks go, `{device}` is filled in
+    pub ack_topic: String,
+    /// device -> location id
+    pub devices: HashMap<String, i32>,
+}
+
+impl MqttConfig {
+    /// MQTT_URL (`mqtt://host:port`), MQTT_CLIENT_ID, MQTT_TOPIC, MQTT_ACK_TOPIC and
+    /// MQTT_DEVICES (`pod6-door=6,library=8`)
+    pub fn from_env() -> Option<Self> {
+        let url = std::env::var("MQTT_URL").ok()?;
+        let address = url.trim_start_matches("mqtt://").trim_end_matches('/');
+        let (host, port) = match address.rsplit_once(':') {
+            Some((host, port)) => (host.to_owned(), port.parse().ok()?),
+            None => (address.to_owned(), 1883),
+        };
+        let devices = std::env::var("MQTT_DEVICES")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|pair| {
+                let (device, location) = pair.split_once('=')?;
+                Some((device.trim().to_owned(), location.trim().parse().ok()?))
+            })
+            .collect();
+        Some(Self {
+            host,
+            port,
+            client_id: std::env::var("MQTT_CLIENT_ID").unwrap_or("mvscan-server".to_string()),
+            topic: std::env::var("MQTT_TOPIC").unwrap_or("scanners/+/scan".to_string()),
+            ack_topic: std::env::var("MQTT_ACK_TOPIC")
+                .unwrap_or("scanners/{device}/ack".to_string()),
+            devices,
+        })
+    }
+
+    /// The level of `topic` matching the subscription's `+`
+    pub fn device_of<'a>(&self, topic: &'a str) -> Option<&'a str> {
+        let wildcard = self.topic.split('/').position(|level| level == "+")?;
+        topic.split('/').nth(wildcard)
+    }
+}
+
+/// A scan as a device publishes it. A bare badge id is accepted as well.
+#[derive(Debug, Deserialize)]
+pub struct MqttScan {
+    pub rfid: String,
+    /// used when the device has no location configured
+    pub location: Option<i32>,
+    pub scanned_at: Option<NaiveDateTime>,
+    /// message id, redeliveries with the same id are applied once
+    pub id: Option<String>,
+}
+
+impl MqttScan {
+    pub fn parse(payload: &[u8]) -> Option<Self> {
+        if let Ok(scan) = serde_json::from_slice(payload) {
+            return Some(scan);
+        }
+        let rfid = std::str::from_utf8(payload).ok()?.trim();
+        (!rfid.is_empty()).then(|| Self {
+            rfid: rfid.to_owned(),
+            location: None,
+            scanned_at: None,
+            id: None,
+        })
+    }
+}
+
+struct Ingest {
+    db: DatabaseConnection,
+    config: MqttConfig,
+    debouncer: Arc<Debouncer>,
+    clock: Arc<ScanClock>,
+    client: AsyncClient,
+}
+
+/// Subscribe and feed every scan through the same pipeline as POST
+/// /api/timestamps until the process exits, reconnecting as needed
+pub async fn run(
+    db: DatabaseConnection,
+    config: MqttConfig,
+    debouncer: Arc<Debouncer>,
+    clock: Arc<ScanClock>,
+) {
+    let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    // a scan is only acked once it's applied, one the server didn't get to
+    // before it stopped is delivered again when the session resumes
+    options.set_manual_acks(true);
+    options.set_clean_session(false);
+    // each scan in flight sends an ack reply and a PUBACK, so workers never
+    // wait on the client while the event loop waits on them
+    let (client, mut eventloop): (AsyncClient, EventLoop) =
+        AsyncClient::new(options, 2 * MAX_IN_FLIGHT + 8);
+    let ingest = Arc::new(Ingest {
+        db,
+        config,
+        debouncer,
+        clock,
+        client,
+    });
+    log::info!(
+        "MQTT ingestion from {}:{} on {}",
+        ingest.config.host,
+        ingest.config.port,
+        ingest.config.topic
+    );
+    // one worker per device, so each device's scans are applied in the order
+    // it published them while devices don't hold each other up
+    let mut workers: HashMap<String, Sender<(Publish, OwnedSemaphorePermit)>> = HashMap::new();
+    let in_flight = Arc::new(Semaphore::new(MAX_IN_FLIGHT));
+    let mut connected = true;
+    loop {
+        match eventloop.poll().await {
+            // the broker may not have kept the session, so renew them on every connect
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                log::info!("MQTT connected");
+                connected = true;
+                if let Err(err) = ingest
+                    .client
+                    .subscribe(&ingest.config.topic, QoS::AtLeastOnce)
+                    .await
+                {
+                    log::error!("MQTT subscribe failed: {}", err);
+                }
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                // waiting here stops polling, which is the backpressure
+                let permit = in_flight
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("semaphore is never closed");
+                let device = ingest.device_of(&publish);
+                let worker = workers
+                    .entry(device)
+                    .or_insert_with(|| spawn_worker(ingest.clone()));
+                if let Err(unsent) = worker.send((publish, permit)).await {
+                    log::error!("MQTT worker gone, dropping scan on {}", unsent.0 .0.topic);
+                }
+            }
+            Ok(_) => {}
+            Err(err) => {
+                // retry every second, but only say so once per outage
+                if connected {
+                    log::warn!("MQTT connection error, retrying: {}", err);
+                    connected = false;
+                }
+                actix_web::rt::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+    }
+}
+
+fn spawn_worker(ingest: Arc<Ingest>) -> Sender<(Publish, OwnedSemaphorePermit)> {
+    let (sender, mut receiver) = mpsc::channel::<(Publish, OwnedSemaphorePermit)>(DEVICE_QUEUE);
+    actix_web::rt::spawn(async move {
+        while let Some((publish, _permit)) = receiver.recv().await {
+            ingest.handle(publish).await;
+        }
+    });
+    sender
+}
+
+impl Ingest {
+    fn device_of(&self, publish: &Publish) -> String {
+        self.config
+            .device_of(&publish.topic)
+            .unwrap_or_default()
+            .to_owned()
+    }
+
+    async fn handle(&self, publish: Publish) {
+        let device = self.device_of(&publish);
+        // applied, refused or unreadable are all final. A scan the database
+        // didn't take is tried again before the device's next one, so its
+        // scans still toggle residents in the order they were published.
+        let mut wait = RETRY_BASE;
+        let ack = loop {
+            match self.apply(&device, &publish.payload).await {
+                Ok(ack) => break ack,
+                Err(err) if err.is::<DbErr>() => {
+                    log::warn!(
+                        "MQTT scan from {} failed, retrying in {:?}: {}",
+                        device,
+                        wait,
+                        err
+                    );
+                    actix_web::rt::time::sleep(wait).await;
+                    wait = (wait * 2).min(RETRY_MAX);
+                }
+                Err(err) => {
+                    log::error!("MQTT scan from {} failed: {}", device, err);
+                    break json!(Response::<String>::from_error(&err.to_string()));
+                }
+            }
+        };
+        let topic = self.config.ack_topic.replace("{device}", &device);
+        if let Err(err) = self
+            .client
+            .publish(topic, QoS::AtLeastOnce, false, ack.to_string())
+            .await
+        {
+            log::error!("MQTT ack to {} failed: {}", device, err);
+        }
+        if let Err(err) = self.client.ack(&publish).await {
+            log::error!("MQTT PUBACK to {} failed: {}", device, err);
+        }
+    }
+
+    async fn apply(
+        &self,
+        device: &str,
+        payload: &[u8],
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        let Some(scan) = MqttScan::parse(payload) else {
+            return Err("Unreadable scan payload".into());
+        };
+        let Some(location) = self.config.devices.get(device).copied().or(scan.location) else {
+            return Err(format!("No location configured for device {}", device).into());
+        };
+        let key = scan.id.as_ref().map(|id| format!("mqtt-{}-{}", device, id));
+        let post = PostTimestamp {
+            rfid: scan.rfid.clone(),
+            location,
+            scanner: Some(device.to_owned()),
+            scanned_at: scan.scanned_at,
+        };
+        let (_, mut body) = apply_scan(
+            &self.db,
+            &post,
+            key.as_deref(),
+            &self.debouncer,
+            &self.clock,
+        )
+        .await?;
+        // let the device match the ack to what it sent
+        if let Value::Object(fields) = &mut body {
+            fields.insert("id".to_string(), json!(scan.id));
+            fields.insert("rfid".to_string(), json!(scan.rfid));
+        }
+        Ok(body)
+    }
+}
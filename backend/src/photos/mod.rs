@@ -0,0 +1,175 @@
+pub mod storage;
+
+use image::{error::ImageError, imageops::FilterType, DynamicImage, ImageFormat, ImageReader};
+use std::fmt::{Display, Formatter};
+use std::io::Cursor;
+use uuid::Uuid;
+
+/// Largest upload accepted, in bytes
+pub const MAX_BYTES: usize = 5 * 1024 * 1024;
+/// Smallest and largest width or height of a photo
+pub const MIN_SIDE: u32 = 64;
+pub const MAX_SIDE: u32 = 4096;
+/// Thumbnails fit in a square this many pixels on a side
+pub const THUMB_SIDE: u32 = 160;
+/// Photos are served from here, followed by their storage key
+pub const URL_PREFIX: &str = "/api/photos/";
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PhotoError {
+    Empty,
+    TooLarge(usize),
+    UnknownFormat,
+    Unsupported(String),
+    Corrupt(String),
+    Dimensions(u32, u32),
+}
+
+impl Display for PhotoError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PhotoError::Empty => write!(f, "No photo was uploaded"),
+            PhotoError::TooLarge(size) => write!(
+                f,
+                "Photo is {} bytes, the limit is {} bytes",
+                size, MAX_BYTES
+            ),
+            PhotoError::UnknownFormat => write!(f, "Photo must be a PNG or JPEG image"),
+            PhotoError::Unsupported(what) => write!(f, "Unsupported photo: {}", what),
+            PhotoError::Corrupt(what) => write!(f, "Damaged photo: {}", what),
+            PhotoError::Dimensions(width, height) => write!(
+                f,
+                "Photo is {}x{}, it must be between {} and {} pixels on each side",
+                width, height, MIN_SIDE, MAX_SIDE
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PhotoError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Png,
+    Jpeg,
+}
+
+impl Format {
+    /// Tell the format from the file's first bytes, whatever the client says it is
+    pub fn sniff(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(PNG_SIGNATURE) {
+            Some(Format::Png)
+        } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(Format::Jpeg)
+        } else {
+            None
+        }
+    }
+
+    fn image_format(self) -> ImageFormat {
+        match self {
+            Format::Png => ImageFormat::Png,
+            Format::Jpeg => ImageFormat::Jpeg,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Png => "png",
+            Format::Jpeg => "jpg",
+        }
+    }
+
+    /// Content type of a stored file, from its key
+    pub fn content_type(key: &str) -> &'static str {
+        if key.ends_with(".jpg") {
+            "image/jpeg"
+        } else {
+            "image/png"
+        }
+    }
+}
+
+impl From<ImageError> for PhotoError {
+    fn from(err: ImageError) -> Self {
+        match err {
+            ImageError::Unsupported(err) => PhotoError::Unsupported(err.to_string()),
+            err => PhotoError::Corrupt(err.to_string()),
+        }
+    }
+}
+
+/// Turn away images too small to recognise anyone in or too big to decode safely
+fn check_size(width: u32, height: u32) -> Result<(), PhotoError> {
+    if (MIN_SIDE..=MAX_SIDE).contains(&width) && (MIN_SIDE..=MAX_SIDE).contains(&height) {
+        Ok(())
+    } else {
+        Err(PhotoError::Dimensions(width, height))
+    }
+}
+
+/// An upload that passed validation, with its thumbnail made
+pub struct Prepared {
+    pub format: Format,
+    pub width: u32,
+    pub height: u32,
+    /// the thumbnail as a PNG
+    pub thumbnail: Vec<u8>,
+}
+
+/// Check that an upload is a whole PNG or JPEG of a sensible size, and make its thumbnail
+pub fn prepare(bytes: &[u8]) -> Result<Prepared, PhotoError> {
+    if bytes.is_empty() {
+        return Err(PhotoError::Empty);
+    }
+    if bytes.len() > MAX_BYTES {
+        return Err(PhotoError::TooLarge(bytes.len()));
+    }
+    let format = Format::sniff(bytes).ok_or(PhotoError::UnknownFormat)?;
+    let reader = || ImageReader::with_format(Cursor::new(bytes), format.image_format());
+    // the header alone says whether the pixels are worth decoding
+    let (width, height) = reader().into_dimensions()?;
+    check_size(width, height)?;
+    let image = reader().decode()?;
+    Ok(Prepared {
+        format,
+        width,
+        height,
+        thumbnail: thumbnail(&image)?,
+    })
+}
+
+/// The image shrunk to fit in a [`THUMB_SIDE`] pixel square, as an 8 bit RGB
+/// PNG. Images already small enough keep their size.
+fn thumbnail(image: &DynamicImage) -> Result<Vec<u8>, PhotoError> {
+    let small = if image.width().max(image.height()) > THUMB_SIDE {
+        image.resize(THUMB_SIDE, THUMB_SIDE, FilterType::Triangle)
+    } else {
+        image.clone()
+    };
+    let mut png = Vec::new();
+    DynamicImage::ImageRgb8(small.to_rgb8())
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+/// The storage key behind a photo URL
+pub fn key_of(url: &str) -> Option<&str> {
+    url.strip_prefix(URL_PREFIX)
+}
+
+/// Storage keys for a resident's photo and its thumbnail. Every upload gets new
+/// keys, even two in the same instant, so a URL always means the same picture
+/// and can be cached for good.
+pub fn keys_for(resident_id: i32, format: Format) -> (String, String) {
+    let upload = Uuid::new_v4().simple();
+    (
+        format!("resident-{}-{}.{}", resident_id, upload, format.extension()),
+        format!("resident-{}-{}-thumb.png", resident_id, upload),
+    )
+}
+
+pub fn url_for(key: &str) -> String {
+    format!("{}{}", URL_PREFIX, key)
+}
@@ -0,0 +1,65 @@
+use std::io;
+use std::path::PathBuf;
+
+/// Where photo files live. Keys are flat file names made up by the server.
+pub trait PhotoStorage: Send + Sync {
+    fn put(&self, key: &str, bytes: &[u8]) -> io::Result<()>;
+    /// `None` when there is nothing stored under `key`
+    fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>>;
+    /// removing a key that isn't there is not an error
+    fn delete(&self, key: &str) -> io::Result<()>;
+}
+
+/// Photos kept in a directory on the server's disk, PHOTO_DIR or `./photos`
+pub struct LocalDisk {
+    root: PathBuf,
+}
+
+impl LocalDisk {
+    pub fn new(root: impl Into<PathBuf>) -> io::Result<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    pub fn from_env() -> io::Result<Self> {
+        Self::new(std::env::var("PHOTO_DIR").unwrap_or("photos".to_string()))
+    }
+
+    fn path(&self, key: &str) -> io::Result<PathBuf> {
+        let safe = !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            && !key.starts_with('.');
+        if !safe {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "bad photo key"));
+        }
+        Ok(self.root.join(key))
+    }
+}
+
+impl PhotoStorage for LocalDisk {
+    fn put(&self, key: &str, bytes: &[u8]) -> io::Result<()> {
+        // write aside and rename, so a reader never sees half a file
+        let path = self.path(key)?;
+        let partial = path.with_extension("part");
+        std::fs::write(&partial, bytes)?;
+        std::fs::rename(partial, path)
+    }
+
+    fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        match std::fs::read(self.path(key)?) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn delete(&self, key: &str) -> io::Result<()> {
+        match std::fs::remove_file(self.path(key)?) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use super::{escape_html, pdf::Document};
+use crate::models::timestamps::timestamps_within;
+use chrono::{Duration, NaiveDateTime};
+use entity::{locations::Entity as Locations, residents::Entity as Residents, timestamps};
+use sea_orm::{ConnectionTrait, DbErr, EntityTrait, QueryOrder};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Scans that reached the server this long after they were read are marked
+const LATE_MINUTES: i64 = 5;
+
+/// One scan into a location, at the time it was scanned
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub ts: NaiveDateTime,
+    pub received_at: NaiveDateTime,
+    pub name: String,
+    pub doc: String,
+    pub room: String,
+}
+
+impl Entry {
+    fn time(&self) -> String {
+        let time = self.ts.format(TIME_FORMAT).to_string();
+        if self.received_at - self.ts > Duration::minutes(LATE_MINUTES) {
+            time + " *"
+        } else {
+            time
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UnitGroup {
+    pub unit: i32,
+    pub unit_name: String,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LocationGroup {
+    pub location: i32,
+    pub name: String,
+    pub units: Vec<UnitGroup>,
+}
+
+impl LocationGroup {
+    pub fn total(&self) -> usize {
+        self.units.iter().map(|unit| unit.entries.len()).sum()
+    }
+}
+
+/// Every movement from `from` to `to`, grouped by the location moved to and then
+/// by the resident's housing unit
+#[derive(Debug, Clone)]
+pub struct MovementLog {
+    pub title: String,
+    pub from: NaiveDateTime,
+    pub to: NaiveDateTime,
+    pub locations: Vec<LocationGroup>,
+    pub residents: usize,
+}
+
+impl MovementLog {
+    pub async fn build<C: ConnectionTrait>(
+        db: &C,
+        title: String,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> Result<Self, DbErr> {
+        let scans = timestamps_within(from, to)
+            .order_by_asc(timestamps::Column::Ts)
+            .find_also_related(Residents)
+            .all(db)
+            .await?;
+        let names: HashMap<i32, String> = Locations::find()
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|loc| (loc.id, loc.name))
+            .collect();
+        let name_of = |id: i32| names.get(&id).cloned().unwrap_or_else(|| id.to_string());
+
+        let mut grouped: BTreeMap<i32, BTreeMap<i32, Vec<Entry>>> = BTreeMap::new();
+        let mut residents = HashSet::new();
+        for (ts, resident) in scans {
+            let Some(resident) = resident else { continue };
+            residents.insert(resident.id);
+            grouped
+                .entry(ts.location)
+                .or_default()
+                .entry(resident.unit)
+                .or_default()
+                .push(Entry {
+                    ts: ts.ts,
+                    received_at: ts.received_at,
+                    name: resident.name,
+                    doc: resident.doc,
+                    room: resident.room,
+                });
+        }
+        let locations = grouped
+            .into_iter()
+            .map(|(location, units)| LocationGroup {
+                location,
+                name: name_of(location),
+                units: units
+                    .into_iter()
+                    .map(|(unit, entries)| UnitGroup {
+                        unit,
+                        unit_name: name_of(unit),
+                        entries,
+                    })
+                    .collect(),
+            })
+            .collect();
+        Ok(Self {
+            title,
+            from,
+            to,
+            locations,
+            residents: residents.len(),
+        })
+    }
+
+    pub fn total(&self) -> usize {
+        self.locations.iter().map(LocationGroup::total).sum()
+    }
+
+    fn period(&self) -> String {
+        format!(
+            "{} to {}",
+            self.from.format(TIME_FORMAT),
+            self.to.format(TIME_FORMAT)
+        )
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "{} movements by {} residents. * uploaded more than {} minutes after it was scanned",
+            self.total(),
+            self.residents,
+            LATE_MINUTES
+        )
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+             <style>body{{font-family:sans-serif}}table{{border-collapse:collapse;margin-bottom:1em}}\
+             th,td{{border:1px solid #999;padding:2px 8px;text-align:left}}</style>\n</head>\n<body>\n\
+             <h1>{title}</h1>\n<p>{period}</p>\n<p>{summary}</p>\n",
+            title = escape_html(&self.title),
+            period = self.period(),
+            summary = self.summary(),
+        );
+        for location in &self.locations {
+            html.push_str(&format!(
+                "<h2>{} ({} movements)</h2>\n",
+                escape_html(&location.name),
+                location.total()
+            ));
+            for unit in &location.units {
+                html.push_str(&format!(
+                    "<h3>Unit {} ({})</h3>\n<table>\n<tr><th>Time</th><th>Name</th><th>DOC</th><th>Room</th></tr>\n",
+                    escape_html(&unit.unit_name),
+                    unit.entries.len()
+                ));
+                for entry in &unit.entries {
+                    html.push_str(&format!(
+                        "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                        entry.time(),
+                        escape_html(&entry.name),
+                        escape_html(&entry.doc),
+                        escape_html(&entry.room)
+                    ));
+                }
+                html.push_str("</table>\n");
+            }
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    pub fn to_pdf(&self) -> Vec<u8> {
+        let columns = [0.0, 110.0, 320.0, 410.0];
+        let mut doc = Document::new();
+        doc.line(&self.title, true, 0.0);
+        doc.line(&self.period(), false, 0.0);
+        doc.line(&self.summary(), false, 0.0);
+        for location in &self.locations {
+            doc.gap();
+            doc.line(
+                &format!("{} ({} movements)", location.name, location.total()),
+                true,
+                0.0,
+            );
+            for unit in &location.units {
+                doc.line(
+                    &format!("Unit {} ({})", unit.unit_name, unit.entries.len()),
+                    true,
+                    10.0,
+                );
+                let header = ["Time", "Name", "DOC", "Room"];
+                doc.row(&cells(&columns, &header), true);
+                for entry in &unit.entries {
+                    let time = entry.time();
+                    let row = [time.as_str(), &entry.name, &entry.doc, &entry.room];
+                    doc.row(&cells(&columns, &row), false);
+                }
+            }
+        }
+        doc.finish()
+    }
+}
+
+// table cells are indented under the unit heading
+fn cells<'a>(columns: &[f32; 4], texts: &[&'a str; 4]) -> Vec<(f32, &'a str)> {
+    columns
+        .iter()
+        .zip(texts.iter())
+        .map(|(x, text)| (x + 20.0, *text))
+        .collect()
+}
@@ -0,0 +1,183 @@
+//! Just enough PDF to print a text report: Letter pages, the built-in
+//! Helvetica faces, and automatic page breaks. Text outside WinAnsi is
+//! replaced with `?`.
+
+const PAGE_WIDTH: f32 = 612.0;
+const PAGE_HEIGHT: f32 = 792.0;
+const MARGIN: f32 = 50.0;
+const FONT_SIZE: f32 = 10.0;
+const LINE_HEIGHT: f32 = 14.0;
+
+pub struct Document {
+    pages: Vec<String>,
+    current: String,
+    y: f32,
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Document {
+    pub fn new() -> Self {
+        Self {
+            pages: Vec::new(),
+            current: String::new(),
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    /// Write one line of text, `indent` points in from the left margin
+    pub fn line(&mut self, text: &str, bold: bool, indent: f32) {
+        if self.y < MARGIN {
+            self.page_break();
+        }
+        let font = if bold { "F2" } else { "F1" };
+        self.current.push_str(&format!(
+            "BT /{} {} Tf {:.1} {:.1} Td ({}) Tj ET\n",
+            font,
+            FONT_SIZE,
+            MARGIN + indent,
+            self.y,
+            escape(text)
+        ));
+        self.y -= LINE_HEIGHT;
+    }
+
+    /// Write cells starting at the given x offsets from the left margin
+    pub fn row(&mut self, cells: &[(f32, &str)], bold: bool) {
+        if self.y < MARGIN {
+            self.page_break();
+        }
+        let y = self.y;
+        for (x, text) in cells {
+            self.line(text, bold, *x);
+            self.y = y;
+        }
+        self.y -= LINE_HEIGHT;
+    }
+
+    pub fn gap(&mut self) {
+        self.y -= LINE_HEIGHT / 2.0;
+    }
+
+    pub fn page_break(&mut self) {
+        self.pages.push(std::mem::take(&mut self.current));
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        if !self.current.is_empty() || self.pages.is_empty() {
+            self.pages.push(std::mem::take(&mut self.current));
+        }
+        // 1 catalog, 2 page tree, 3 and 4 fonts, then a page and its content per page
+        let mut objects: Vec<String> = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            String::new(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+                .to_string(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+                .to_string(),
+        ];
+        let mut kids = Vec::new();
+        for content in &self.pages {
+            let page_id = objects.len() + 1;
+            kids.push(format!("{} 0 R", page_id));
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                page_id + 1
+            ));
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{}endstream",
+                content.len(),
+                content
+            ));
+        }
+        objects[1] = format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            kids.len()
+        );
+
+        let mut out = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).into_bytes());
+        }
+        let xref = out.len();
+        out.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+        for offset in offsets {
+            out.extend(format!("{:010} 00000 n \n", offset).into_bytes());
+        }
+        out.extend(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                objects.len() + 1,
+                xref
+            )
+            .into_bytes(),
+        );
+        out
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '(' | ')' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => match win_ansi(c) {
+                Some(byte @ b' '..=b'~') => escaped.push(byte as char),
+                // the stream stays ASCII, anything above is written as an octal escape
+                Some(byte) => escaped.push_str(&format!("\\{:03o}", byte)),
+                None => escaped.push('?'),
+            },
+        }
+    }
+    escaped
+}
+
+/// The WinAnsiEncoding byte the fonts use for `c`, if it has one
+fn win_ansi(c: char) -> Option<u8> {
+    let byte = match c {
+        ' '..='~' | '\u{A0}'..='\u{FF}' => c as u8,
+        '€' => 0x80,
+        '‚' => 0x82,
+        'ƒ' => 0x83,
+        '„' => 0x84,
+        '…' => 0x85,
+        '†' => 0x86,
+        '‡' => 0x87,
+        'ˆ' => 0x88,
+        '‰' => 0x89,
+        'Š' => 0x8A,
+        '‹' => 0x8B,
+        'Œ' => 0x8C,
+        'Ž' => 0x8E,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        '˜' => 0x98,
+        '™' => 0x99,
+        'š' => 0x9A,
+        '›' => 0x9B,
+        'œ' => 0x9C,
+        'ž' => 0x9E,
+        'Ÿ' => 0x9F,
+        _ => return None,
+    };
+    Some(byte)
+}
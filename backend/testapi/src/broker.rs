@@ -0,0 +1,207 @@
+//! Just enough of an MQTT 3.1.1 broker to stand in for mosquitto in tests:
+//! QoS 0 delivery to subscribers, `+` and `#` wildcards, no sessions or
+//! retained messages. Also a small client to play the scanner.
+
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+type Subscriptions = Arc<Mutex<Vec<(String, TcpStream)>>>;
+
+pub struct Broker {
+    subscriptions: Subscriptions,
+}
+
+impl Broker {
+    pub fn start(port: u16) -> Self {
+        let listener = TcpListener::bind(("127.0.0.1", port)).expect("broker port in use");
+        let subscriptions: Subscriptions = Arc::default();
+        let shared = subscriptions.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let shared = shared.clone();
+                std::thread::spawn(move || serve(stream, shared));
+            }
+        });
+        Self { subscriptions }
+    }
+
+    /// Wait until some client subscribed to exactly `filter`
+    pub fn wait_for_subscriber(&self, filter: &str, timeout: Duration) -> bool {
+        let start = Instant::now();
+        while start.elapsed() < timeout {
+            if self
+                .subscriptions
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|(subscribed, _)| subscribed == filter)
+            {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        false
+    }
+}
+
+fn serve(mut stream: TcpStream, subscriptions: Subscriptions) {
+    while let Ok((header, body)) = read_packet(&mut stream) {
+        let reply = match header >> 4 {
+            // CONNECT
+            1 => vec![0x20, 2, 0, 0],
+            // PUBLISH
+            3 => {
+                let qos = (header >> 1) & 3;
+                let topic_len = u16::from_be_bytes([body[0], body[1]]) as usize;
+                let topic = String::from_utf8_lossy(&body[2..2 + topic_len]).to_string();
+                let mut payload_start = 2 + topic_len;
+                let mut reply = Vec::new();
+                if qos > 0 {
+                    reply = vec![0x40, 2, body[payload_start], body[payload_start + 1]];
+                    payload_start += 2;
+                }
+                let forward = publish_packet(&topic, &body[payload_start..]);
+                for (filter, subscriber) in subscriptions.lock().unwrap().iter_mut() {
+                    if matches(filter, &topic) {
+                        let _ = subscriber.write_all(&forward);
+                    }
+                }
+                reply
+            }
+            // SUBSCRIBE
+            8 => {
+                let mut granted = Vec::new();
+                let mut at = 2;
+                while at + 2 <= body.len() {
+                    let len = u16::from_be_bytes([body[at], body[at + 1]]) as usize;
+                    let filter = String::from_utf8_lossy(&body[at + 2..at + 2 + len]).to_string();
+                    at += len + 3;
+                    subscriptions
+                        .lock()
+                        .unwrap()
+                        .push((filter, stream.try_clone().unwrap()));
+                    granted.push(0);
+                }
+                let mut reply = vec![0x90, 2 + granted.len() as u8, body[0], body[1]];
+                reply.extend(granted);
+                reply
+            }
+            // PINGREQ
+            12 => vec![0xd0, 0],
+            // DISCONNECT
+            14 => break,
+            _ => Vec::new(),
+        };
+        if stream.write_all(&reply).is_err() {
+            break;
+        }
+    }
+}
+
+fn matches(filter: &str, topic: &str) -> bool {
+    let mut topic = topic.split('/');
+    for level in filter.split('/') {
+        match (level, topic.next()) {
+            ("#", _) => return true,
+            ("+", Some(_)) => {}
+            (level, Some(part)) if level == part => {}
+            _ => return false,
+        }
+    }
+    topic.next().is_none()
+}
+
+fn read_packet(stream: &mut TcpStream) -> io::Result<(u8, Vec<u8>)> {
+    let mut byte = [0u8; 1];
+    stream.read_exact(&mut byte)?;
+    let header = byte[0];
+    let (mut length, mut shift) = (0usize, 0);
+    loop {
+        stream.read_exact(&mut byte)?;
+        length |= ((byte[0] & 0x7f) as usize) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    let mut body = vec![0u8; length];
+    stream.read_exact(&mut body)?;
+    Ok((header, body))
+}
+
+fn with_length(header: u8, body: Vec<u8>) -> Vec<u8> {
+    let mut packet = vec![header];
+    let mut length = body.len();
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if length == 0 {
+            break;
+        }
+    }
+    packet.extend(body);
+    packet
+}
+
+fn string(value: &str) -> Vec<u8> {
+    let mut bytes = (value.len() as u16).to_be_bytes().to_vec();
+    bytes.extend(value.as_bytes());
+    bytes
+}
+
+fn publish_packet(topic: &str, payload: &[u8]) -> Vec<u8> {
+    let mut body = string(topic);
+    body.extend(payload);
+    with_length(0x30, body)
+}
+
+/// A device talking to the broker
+pub struct Client {
+    stream: TcpStream,
+}
+
+impl Client {
+    pub fn connect(port: u16, id: &str) -> Self {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let mut body = string("MQTT");
+        // protocol level 4, clean session, 60s keep alive
+        body.extend([4, 0x02, 0, 60]);
+        body.extend(string(id));
+        stream.write_all(&with_length(0x10, body)).unwrap();
+        read_packet(&mut stream).unwrap();
+        Self { stream }
+    }
+
+    pub fn subscribe(&mut self, filter: &str) {
+        let mut body = vec![0, 1];
+        body.extend(string(filter));
+        body.push(0);
+        self.stream.write_all(&with_length(0x82, body)).unwrap();
+        read_packet(&mut self.stream).unwrap();
+    }
+
+    pub fn publish(&mut self, topic: &str, payload: &str) {
+        self.stream
+            .write_all(&publish_packet(topic, payload.as_bytes()))
+            .unwrap();
+    }
+
+    /// The next message delivered to this client
+    pub fn next_message(&mut self, timeout: Duration) -> Option<(String, Vec<u8>)> {
+        self.stream.set_read_timeout(Some(timeout)).unwrap();
+        loop {
+            let (header, body) = read_packet(&mut self.stream).ok()?;
+            if header >> 4 == 3 {
+                let topic_len = u16::from_be_bytes([body[0], body[1]]) as usize;
+                let topic = String::from_utf8_lossy(&body[2..2 + topic_len]).to_string();
+                return Some((topic, body[2 + topic_len..].to_vec()));
+            }
+        }
+    }
+}
@@ -1,3 +1,10 @@
+#[cfg(test)]
+mod broker;
+
+#[cfg(test)]
+mod hook_stub;
+
+#[cfg(test)]
 pub mod testapi {
 
     use reqwest::blocking::Response;
@@ -84,6 +91,23 @@ pub mod testapi {
         assert_eq!(response.status().as_u16(), 200);
     }
 
+    #[test]
+    fn test_residents_contacts() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let ip = format!("http://{}:8080/api", ip);
+        // replays the whole window, so allow more than the default timeout
+        let response = reqwest::blocking::Client::new()
+            .get(format!(
+                "{}/residents/555555666555555/contacts?from=2023-11-01T00:00:00&to=2099-01-01T00:00:00&min_overlap=5",
+                ip
+            ))
+            .timeout(Duration::from_millis(200))
+            .send()
+            .expect("Failed to execute request");
+        assert_eq!(response.status().as_u16(), 200);
+        assert!(response.json::<Value>().unwrap()["data"].is_array());
+    }
+
     #[test]
     fn test_locations_index() {
         let response = make_request("locations", reqwest::Method::GET, None);
@@ -110,6 +134,39 @@ pub mod testapi {
         assert_eq!(resp.status().as_u16(), 200);
     }
 
+    #[test]
+    fn test_locations_update_capacity() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let ip = format!("http://{}:8080/api", ip);
+        let capacity = json!({"capacity": 30, "level_caps": [{"level": 4, "capacity": 10}]});
+        let resp = reqwest::blocking::Client::new()
+            .patch(format!("{}/locations/19", ip))
+            .json(&capacity)
+            .timeout(Duration::from_millis(50))
+            .send()
+            .expect("Failed to execute request");
+        assert_eq!(resp.status().as_u16(), 200);
+        let body = resp.json::<Value>().unwrap();
+        assert_eq!(body["data"][0]["capacity"], 30);
+        assert!(body["data"][0]["occupancy"].is_number());
+
+        // a negative cap is turned away and leaves the capacity as it was
+        let rejected = reqwest::blocking::Client::new()
+            .patch(format!("{}/locations/19", ip))
+            .json(&json!({"capacity": 20, "level_caps": [{"level": 4, "capacity": -1}]}))
+            .timeout(Duration::from_secs(2))
+            .send()
+            .expect("Failed to execute request")
+            .json::<Value>()
+            .unwrap();
+        assert_eq!(rejected["success"], false);
+        let location = reqwest::blocking::get(format!("{}/locations/19", ip))
+            .unwrap()
+            .json::<Value>()
+            .unwrap();
+        assert_eq!(location["data"][0]["capacity"], 30);
+    }
+
     #[test]
     fn test_locations_timestamps() {
         let response = make_request("locations/8/timestamps", reqwest::Method::GET, None);
@@ -132,6 +189,30 @@ pub mod testapi {
         assert_eq!(response.status().as_u16(), 200);
     }
 
+    #[test]
+    fn test_locations_residents_rollup() {
+        // HOUSING_BUILDING contains the housing units
+        let response = make_request(
+            "locations/100/residents?current=true&rollup=true",
+            reqwest::Method::GET,
+            None,
+        );
+        assert_eq!(response.status().as_u16(), 200);
+        let body = response.json::<Value>().unwrap();
+        assert!(!body["data"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_locations_occupants() {
+        let response = make_request(
+            "locations/13/occupants?at=2099-01-01T00:00:00",
+            reqwest::Method::GET,
+            None,
+        );
+        assert_eq!(response.status().as_u16(), 200);
+        assert!(response.json::<Value>().unwrap()["data"].is_array());
+    }
+
     #[test]
     fn test_timestamps_index() {
         // TestTimestampsController
@@ -173,4 +254,1295 @@ pub mod testapi {
         );
         assert_eq!(response.status().as_u16(), 200);
     }
+    #[test]
+    fn test_snapshot() {
+        let response = make_request("snapshot?at=2099-01-01 00:00", reqwest::Method::GET, None);
+        assert_eq!(response.status().as_u16(), 200);
+        assert!(response.json::<Value>().unwrap()["data"][0]["occupants"].is_array());
+    }
+
+    #[test]
+    fn test_separations_create() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let ip = format!("http://{}:8080/api", ip);
+        // left over if an earlier run stopped half way
+        let existing = make_request(
+            "separations?rfid=555555111555555",
+            reqwest::Method::GET,
+            None,
+        )
+        .json::<Value>()
+        .unwrap();
+        for sep in existing["data"].as_array().unwrap() {
+            if sep["resident"] == "987654321098765" || sep["separated"] == "987654321098765" {
+                reqwest::blocking::Client::new()
+                    .delete(format!("{}/separations/{}", ip, sep["id"]))
+                    .timeout(Duration::from_secs(2))
+                    .send()
+                    .expect("Failed to execute request");
+            }
+        }
+        let separation = json!({"resident": "555555111555555", "separated": "987654321098765", "reason": "Fight in unit"});
+        let resp = reqwest::blocking::Client::new()
+            .post(format!("{}/separations", ip))
+            .json(&separation)
+            .timeout(Duration::from_millis(50))
+            .send()
+            .expect("Failed to execute request");
+        assert_eq!(resp.status().as_u16(), 201);
+        let created = resp.json::<Value>().unwrap();
+        assert_eq!(created["data"][0]["reason"], "Fight in unit");
+
+        // the same pair either way round is already on record
+        let again = reqwest::blocking::Client::new()
+            .post(format!("{}/separations", ip))
+            .json(&json!({"resident": "987654321098765", "separated": "555555111555555", "reason": "Again"}))
+            .timeout(Duration::from_secs(2))
+            .send()
+            .expect("Failed to execute request")
+            .json::<Value>()
+            .unwrap();
+        assert_eq!(again["success"], false);
+        assert_eq!(again["message"], "These residents are already separated");
+
+        let removed = reqwest::blocking::Client::new()
+            .delete(format!("{}/separations/{}", ip, created["data"][0]["id"]))
+            .timeout(Duration::from_secs(2))
+            .send()
+            .expect("Failed to execute request");
+        assert_eq!(removed.status().as_u16(), 200);
+    }
+
+    #[test]
+    fn test_separations_index() {
+        let response = make_request("separations", reqwest::Method::GET, None);
+        assert_eq!(response.status().as_u16(), 200);
+        assert!(response.json::<Value>().unwrap()["data"].is_array());
+    }
+
+    #[test]
+    fn test_alerts_index() {
+        let response = make_request("alerts?kind=separation", reqwest::Method::GET, None);
+        assert_eq!(response.status().as_u16(), 200);
+        assert!(response.json::<Value>().unwrap()["data"].is_array());
+    }
+
+    #[test]
+    fn test_schedules_create() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let ip = format!("http://{}:8080/api", ip);
+        let schedule = json!({"weekday": 2, "opens_at": "08:00:00", "closes_at": "11:30:00", "allowed_levels": [3, 4]});
+        let resp = reqwest::blocking::Client::new()
+            .post(format!("{}/locations/13/schedules", ip))
+            .json(&schedule)
+            .timeout(Duration::from_millis(50))
+            .send()
+            .expect("Failed to execute request");
+        assert_eq!(resp.status().as_u16(), 201);
+        assert_eq!(resp.json::<Value>().unwrap()["data"][0]["weekday"], 2);
+    }
+
+    #[test]
+    fn test_schedules_empty_window() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let ip = format!("http://{}:8080/api", ip);
+        // it would never cover any time, so it's refused rather than stored
+        let schedule = json!({"weekday": 2, "opens_at": "08:00:00", "closes_at": "08:00:00"});
+        let resp: Value = reqwest::blocking::Client::new()
+            .post(format!("{}/locations/13/schedules", ip))
+            .json(&schedule)
+            .send()
+            .expect("Failed to execute request")
+            .json()
+            .unwrap();
+        assert_eq!(resp["success"], false);
+    }
+
+    #[test]
+    fn test_schedules_index() {
+        let response = make_request("locations/13/schedules", reqwest::Method::GET, None);
+        assert_eq!(response.status().as_u16(), 200);
+        assert!(response.json::<Value>().unwrap()["data"].is_array());
+    }
+
+    #[test]
+    fn test_appointments_create() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let ip = format!("http://{}:8080/api", ip);
+        let appointment = json!({"rfid": "987654321098765", "location": 9, "kind": "medical", "starts_at": "2099-01-01 09:00", "ends_at": "2099-01-01 09:30"});
+        let resp = reqwest::blocking::Client::new()
+            .post(format!("{}/appointments", ip))
+            .json(&appointment)
+            .timeout(Duration::from_millis(50))
+            .send()
+            .expect("Failed to execute request");
+        assert_eq!(resp.status().as_u16(), 201);
+        assert_eq!(
+            resp.json::<Value>().unwrap()["data"][0]["status"],
+            "scheduled"
+        );
+    }
+
+    #[test]
+    fn test_appointments_index() {
+        let response = make_request("appointments?date=2099-01-01", reqwest::Method::GET, None);
+        assert_eq!(response.status().as_u16(), 200);
+        assert!(response.json::<Value>().unwrap()["data"].is_array());
+    }
+
+    #[test]
+    fn test_reports_movement_log() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let ip = format!("http://{}:8080/api", ip);
+        let client = reqwest::blocking::Client::new();
+        let html = client
+            .get(format!("{}/reports/movement-log?shift=day", ip))
+            .timeout(Duration::from_millis(200))
+            .send()
+            .expect("Failed to execute request");
+        assert_eq!(html.status().as_u16(), 200);
+        assert!(html.text().unwrap().contains("Movement log: day shift"));
+        let pdf = client
+            .get(format!("{}/reports/movement-log?format=pdf", ip))
+            .timeout(Duration::from_millis(200))
+            .send()
+            .expect("Failed to execute request");
+        assert_eq!(pdf.status().as_u16(), 200);
+        assert!(pdf.bytes().unwrap().starts_with(b"%PDF"));
+    }
+
+    #[test]
+    fn test_shifts_current() {
+        let response = make_request("shifts/current", reqwest::Method::GET, None);
+        assert_eq!(response.status().as_u16(), 200);
+        assert!(response.json::<Value>().unwrap()["data"][0]["start"].is_string());
+    }
+
+    #[test]
+    fn test_timestamps_index_shift() {
+        let response = make_request("timestamps?shift=night", reqwest::Method::GET, None);
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(response.json::<Value>().unwrap()["success"], true);
+    }
+
+    #[test]
+    fn test_admin_consistency() {
+        let response = make_request("admin/consistency", reqwest::Method::GET, None);
+        assert_eq!(response.status().as_u16(), 200);
+        assert!(response.json::<Value>().unwrap()["data"].is_array());
+    }
+
+    #[test]
+    fn test_admin_consistency_repair() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let ip = format!("http://{}:8080/api", ip);
+        let resp = reqwest::blocking::Client::new()
+            .post(format!(
+                "{}/admin/consistency/repair?rfid=555555666555555",
+                ip
+            ))
+            .timeout(Duration::from_millis(50))
+            .send()
+            .expect("Failed to execute request");
+        assert_eq!(resp.status().as_u16(), 200);
+        assert_eq!(resp.json::<Value>().unwrap()["success"], true);
+    }
+
+    #[test]
+    fn test_timestamps_post_concurrent() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let ip = format!("http://{}:8080/api", ip);
+        let rfid = "444444777444444";
+        // scans of one badge at one location alternate between there and AWAY,
+        // so however they interleave, half of an even number must come back AWAY
+        let scans: Vec<_> = (0..8)
+            .map(|_| {
+                let url = format!("{}/timestamps", ip);
+                std::thread::spawn(move || {
+                    reqwest::blocking::Client::new()
+                        .post(url)
+                        .json(&json!({"rfid": rfid, "location": 5}))
+                        .timeout(Duration::from_secs(2))
+                        .send()
+                        .expect("Failed to execute request")
+                        .json::<Value>()
+                        .unwrap()
+                })
+            })
+            .collect();
+        let away = scans
+            .into_iter()
+            .map(|scan| scan.join().unwrap())
+            .filter(|resp| resp["data"][0]["timestamp"]["location"] == 0)
+            .count();
+        assert_eq!(away, 4);
+
+        let check = make_request(
+            &format!("admin/consistency?rfid={}", rfid),
+            reqwest::Method::GET,
+            None,
+        );
+        assert_eq!(check.json::<Value>().unwrap()["data"], json!([]));
+    }
+
+    #[test]
+    fn test_timestamps_post_concurrent_arrivals() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let api = format!("http://{}:8080/api", ip);
+        let client = reqwest::blocking::Client::new();
+        // a room of one that turns away anyone past its capacity, and residents
+        // of their own, all new each run so nobody is there yet
+        let millis = chrono::Local::now().timestamp_millis();
+        let location = 1_000_000 + (millis % 1_000_000_000) as i32;
+        let created = client
+            .post(format!("{}/locations", api))
+            .json(&json!({"id": location, "name": "Capacity Test", "level": 2, "capacity": 1, "refuse_over_capacity": true}))
+            .timeout(Duration::from_secs(2))
+            .send()
+            .expect("Failed to execute request")
+            .json::<Value>()
+            .unwrap();
+        assert_eq!(created["success"], true);
+        let rfids: Vec<String> = (0..6).map(|n| format!("71{}{}", millis, n)).collect();
+        for rfid in &rfids {
+            let resident = json!({"rfid": rfid, "name": "Capacity, Test", "doc": format!("c{}", rfid), "room": "D-4c", "unit": 4, "current_location": 4, "level": 2});
+            let created = client
+                .post(format!("{}/residents", api))
+                .json(&resident)
+                .timeout(Duration::from_secs(2))
+                .send()
+                .expect("Failed to execute request")
+                .json::<Value>()
+                .unwrap();
+            assert_eq!(created["success"], true);
+        }
+        let scan = |rfid: &str| {
+            reqwest::blocking::Client::new()
+                .post(format!("{}/timestamps", api))
+                .json(&json!({"rfid": rfid, "location": location}))
+                .timeout(Duration::from_secs(2))
+                .send()
+                .expect("Failed to execute request")
+                .json::<Value>()
+                .unwrap()
+        };
+        // all of them arrive at once, only one fits
+        let arrived: Vec<String> = std::thread::scope(|threads| {
+            let scans: Vec<_> = rfids
+                .iter()
+                .map(|rfid| threads.spawn(|| (rfid.clone(), scan(rfid))))
+                .collect();
+            scans
+                .into_iter()
+                .map(|scan| scan.join().unwrap())
+                .filter(|(_, resp)| resp["success"] == true)
+                .map(|(rfid, _)| rfid)
+                .collect()
+        });
+        let occupancy = || {
+            client
+                .get(format!("{}/locations/{}", api, location))
+                .timeout(Duration::from_secs(2))
+                .send()
+                .expect("Failed to execute request")
+                .json::<Value>()
+                .unwrap()["data"][0]["occupancy"]
+                .clone()
+        };
+        assert_eq!(arrived.len(), 1);
+        assert_eq!(occupancy(), 1);
+
+        // the one who got in scans back out, the rest never moved and can go
+        assert_eq!(scan(&arrived[0])["data"][0]["timestamp"]["location"], 0);
+        assert_eq!(occupancy(), 0);
+        for rfid in rfids.iter().filter(|rfid| **rfid != arrived[0]) {
+            let released = client
+                .delete(format!("{}/residents/{}", api, rfid))
+                .timeout(Duration::from_secs(2))
+                .send()
+                .expect("Failed to execute request");
+            assert_eq!(released.status().as_u16(), 204);
+        }
+    }
+
+    #[test]
+    fn test_timestamps_post_idempotency_key() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let url = format!("http://{}:8080/api/timestamps", ip);
+        let key = format!(
+            "test-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        );
+        let post = || {
+            reqwest::blocking::Client::new()
+                .post(&url)
+                .header("Idempotency-Key", &key)
+                .json(&json!({"rfid": "777777888777777", "location": 5}))
+                .timeout(Duration::from_secs(2))
+                .send()
+                .expect("Failed to execute request")
+        };
+        let first = post();
+        assert_eq!(first.status().as_u16(), 201);
+        let first = first.json::<Value>().unwrap();
+        // a retry gets the original answer instead of toggling back out
+        let retry = post();
+        assert_eq!(retry.status().as_u16(), 201);
+        assert_eq!(retry.json::<Value>().unwrap(), first);
+
+        // the key doesn't carry over to another resident's scan
+        let reused = reqwest::blocking::Client::new()
+            .post(&url)
+            .header("Idempotency-Key", &key)
+            .json(&json!({"rfid": "666666000666666", "location": 5}))
+            .timeout(Duration::from_secs(2))
+            .send()
+            .expect("Failed to execute request");
+        assert_eq!(reused.status().as_u16(), 422);
+        assert_eq!(reused.json::<Value>().unwrap()["success"], false);
+    }
+
+    #[test]
+    fn test_timestamps_post_debounce() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let ip = format!("http://{}:8080/api", ip);
+        let client = reqwest::blocking::Client::new();
+        let get = |endpoint: &str| {
+            client
+                .get(format!("{}/{}", ip, endpoint))
+                .timeout(Duration::from_secs(2))
+                .send()
+                .expect("Failed to execute request")
+                .json::<Value>()
+                .unwrap()
+        };
+        let debounce = |seconds: &Value| {
+            let patched = client
+                .patch(format!("{}/locations/7", ip))
+                .json(&json!({ "debounce_seconds": seconds }))
+                .timeout(Duration::from_secs(2))
+                .send()
+                .expect("Failed to execute request");
+            assert_eq!(patched.status().as_u16(), 200);
+        };
+        let before = get("locations/7")["data"][0]["debounce_seconds"].clone();
+        let started_at = get("residents/666666000666666")["data"][0]["current_location"].clone();
+        debounce(&json!(60));
+        // a scanner of its own, so an earlier run's scans aren't still remembered
+        let scanner = format!("door-7a-{}", chrono::Local::now().timestamp_millis());
+        let scan = || {
+            client
+                .post(format!("{}/timestamps", ip))
+                .json(&json!({"rfid": "666666000666666", "location": 7, "scanner": scanner}))
+                .timeout(Duration::from_secs(2))
+                .send()
+                .expect("Failed to execute request")
+                .json::<Value>()
+                .unwrap()
+        };
+        let first = scan();
+        let repeat = scan();
+        debounce(&before);
+        assert_eq!(
+            first["message"],
+            "Resident Timestamp successfully retrieved"
+        );
+        assert_eq!(repeat["success"], true);
+        assert_eq!(repeat["message"], "Duplicate scan ignored");
+        assert_eq!(
+            repeat["data"][0]["timestamp"]["id"],
+            first["data"][0]["timestamp"]["id"]
+        );
+        // the first scan toggled them, the repeat left them there
+        let toggled = if started_at == 7 { 0 } else { 7 };
+        assert_eq!(repeat["data"][0]["resident"]["current_location"], toggled);
+    }
+
+    #[test]
+    fn test_timestamps_post_batch() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let url = format!("http://{}:8080/api/timestamps/batch", ip);
+        let client = reqwest::blocking::Client::new();
+        let started_at = client
+            .get(format!("http://{}:8080/api/residents/999999999999999", ip))
+            .timeout(Duration::from_secs(2))
+            .send()
+            .expect("Failed to execute request")
+            .json::<Value>()
+            .unwrap()["data"][0]["current_location"]
+            .clone();
+        // newer than anything an earlier run recorded, so neither is backfilled
+        let now = chrono::Local::now().naive_local();
+        let (earlier, later) = (
+            now - chrono::Duration::milliseconds(200),
+            now - chrono::Duration::milliseconds(100),
+        );
+        let (earlier, later) = (
+            earlier.format("%Y-%m-%dT%H:%M:%S%.3f").to_string(),
+            later.format("%Y-%m-%dT%H:%M:%S%.3f").to_string(),
+        );
+        // uploaded out of order, applied oldest first, so each toggles the one before
+        let data = json!([
+            {"rfid": "999999999999999", "location": 8, "scanner": "kiosk-2", "scanned_at": later},
+            {"rfid": "999999999999999", "location": 8, "scanner": "kiosk-2", "scanned_at": earlier},
+            {"rfid": "000000000000000", "location": 8, "scanned_at": earlier}
+        ]);
+        let upload = || {
+            let response = client
+                .post(&url)
+                .json(&data)
+                .timeout(Duration::from_secs(2))
+                .send()
+                .expect("Failed to execute request");
+            assert_eq!(response.status().as_u16(), 200);
+            response.json::<Value>().unwrap()["data"].clone()
+        };
+        let results = upload();
+        let (first, second) = if started_at == 8 { (0, 8) } else { (8, 0) };
+        assert_eq!(results[1]["status"], "recorded");
+        assert_eq!(results[1]["movement"]["timestamp"]["location"], first);
+        assert_eq!(results[0]["status"], "recorded");
+        assert_eq!(results[0]["movement"]["timestamp"]["location"], second);
+        let recorded_at = results[0]["movement"]["timestamp"]["ts"].as_str().unwrap();
+        let parse = |ts: &str| chrono::NaiveDateTime::parse_from_str(ts, "%Y-%m-%dT%H:%M:%S%.f");
+        assert_eq!(parse(recorded_at), parse(&later));
+        assert_eq!(results[2]["status"], "unknown_resident");
+
+        // the kiosk didn't hear back and sends the batch again
+        let retried = upload();
+        for index in 0..2 {
+            assert_eq!(retried[index]["status"], "ignored");
+            assert_eq!(
+                retried[index]["movement"]["timestamp"]["id"],
+                results[index]["movement"]["timestamp"]["id"]
+            );
+        }
+
+        // a kiosk that was offline for days still gets its scans at the time they were read
+        let offline = (now - chrono::Duration::days(3))
+            .format("%Y-%m-%dT%H:%M:%S%.3f")
+            .to_string();
+        let backlog = client
+            .post(&url)
+            .json(&json!([{"rfid": "999999999999999", "location": 8, "scanner": "kiosk-2", "scanned_at": offline}]))
+            .timeout(Duration::from_secs(2))
+            .send()
+            .expect("Failed to execute request")
+            .json::<Value>()
+            .unwrap();
+        assert_eq!(backlog["data"][0]["status"], "backfilled");
+        let backfilled_at = backlog["data"][0]["movement"]["timestamp"]["ts"]
+            .as_str()
+            .unwrap();
+        assert_eq!(parse(backfilled_at), parse(&offline));
+        assert!(backlog["data"][0]["movement"]["alerts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|alert| alert["kind"] != "clock_skew"));
+    }
+
+    #[test]
+    fn test_timestamps_post_scanned_at() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let url = format!("http://{}:8080/api/timestamps", ip);
+        let scanned_at = (chrono::Local::now().naive_local() - chrono::Duration::minutes(3))
+            .format("%Y-%m-%dT%H:%M:%S")
+            .to_string();
+        let post = |scanned_at: &str| {
+            reqwest::blocking::Client::new()
+                .post(&url)
+                .json(&json!({"rfid": "333333333333333", "location": 4, "scanned_at": scanned_at}))
+                .timeout(Duration::from_secs(2))
+                .send()
+                .expect("Failed to execute request")
+                .json::<Value>()
+                .unwrap()
+        };
+        // a delayed scan keeps the time it was read
+        let delayed = post(&scanned_at);
+        assert_eq!(delayed["data"][0]["timestamp"]["ts"], scanned_at);
+        assert_ne!(delayed["data"][0]["timestamp"]["received_at"], scanned_at);
+
+        // a clock a day ahead is flagged and the scan goes by the server clock
+        let skewed = (chrono::Local::now().naive_local() + chrono::Duration::days(1))
+            .format("%Y-%m-%dT%H:%M:%S")
+            .to_string();
+        let flagged = post(&skewed);
+        assert_ne!(flagged["data"][0]["timestamp"]["ts"], skewed);
+        assert_eq!(flagged["data"][0]["alerts"][0]["kind"], "clock_skew");
+        assert_eq!(flagged["data"][0]["timestamp"]["scanned_at"], skewed);
+
+        // resending it is recognised by the time the client sent, not the one it was recorded at
+        let resent = post(&skewed);
+        assert_eq!(resent["message"], "Duplicate scan ignored");
+        assert_eq!(
+            resent["data"][0]["timestamp"]["id"],
+            flagged["data"][0]["timestamp"]["id"]
+        );
+    }
+
+    #[test]
+    fn test_bridge_frames() {
+        use mvscan_bridge::frames::{decode, Format, FrameError};
+        // facility 18, card 12345
+        assert_eq!(decode(Format::Wiegand26, "0x246073").unwrap(), "0001191993");
+        assert!(matches!(
+            decode(Format::Wiegand26, "246053"),
+            Err(FrameError::Parity(_))
+        ));
+        assert_eq!(
+            decode(Format::Em4100, "010203040501").unwrap(),
+            "0033752069"
+        );
+        assert!(matches!(
+            decode(Format::Em4100, "010203040502"),
+            Err(FrameError::Checksum(_))
+        ));
+        assert_eq!(
+            decode(Format::Wedge, " 555555231555555").unwrap(),
+            "555555231555555"
+        );
+    }
+
+    #[test]
+    fn test_bridge_pty_scan() {
+        use mvscan_bridge::{
+            client::Poster,
+            frames::{decode, Format},
+            input::{Input, Reader},
+        };
+        use std::io::Write;
+        use std::os::unix::io::FromRawFd;
+
+        // a pseudo-terminal stands in for the reader's serial port
+        let (mut master, mut slave) = (0, 0);
+        let mut name = [0 as libc::c_char; 64];
+        let opened = unsafe {
+            libc::openpty(
+                &mut master,
+                &mut slave,
+                name.as_mut_ptr(),
+                std::ptr::null(),
+                std::ptr::null(),
+            )
+        };
+        assert_eq!(opened, 0);
+        let path = unsafe { std::ffi::CStr::from_ptr(name.as_ptr()) }
+            .to_str()
+            .unwrap()
+            .to_owned();
+        let mut reader = Reader::open(std::path::Path::new(&path), Input::Serial, 9600).unwrap();
+        let mut master = unsafe { std::fs::File::from_raw_fd(master) };
+        // a garbled read and a good one arrive together
+        master
+            .write_all(b"\x02bad-frame\x03\x02555555231555555\r\n")
+            .unwrap();
+
+        let garbled = reader.next_frame().unwrap().unwrap();
+        assert!(decode(Format::Wedge, &garbled).is_err());
+        let frame = reader.next_frame().unwrap().unwrap();
+        let rfid = decode(Format::Wedge, &frame).unwrap();
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let started_at = reqwest::blocking::Client::new()
+            .get(format!("http://{}:8080/api/residents/555555231555555", ip))
+            .timeout(Duration::from_secs(2))
+            .send()
+            .expect("Failed to execute request")
+            .json::<Value>()
+            .unwrap()["data"][0]["current_location"]
+            .clone();
+        let poster = Poster::new(&format!("http://{}:8080/api", ip), 6, "pty-test");
+        let response = poster
+            .post(&rfid, chrono::Local::now().naive_local())
+            .unwrap();
+        assert_eq!(response["success"], true);
+        assert_eq!(response["data"][0]["resident"]["rfid"], "555555231555555");
+        let toggled = if started_at == 6 { 0 } else { 6 };
+        assert_eq!(response["data"][0]["timestamp"]["location"], toggled);
+        assert_eq!(response["data"][0]["timestamp"]["scanner"], "pty-test");
+        unsafe { libc::close(slave) };
+    }
+
+    #[test]
+    fn test_residents_badge_formats() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let ip = format!("http://{}:8080/api", ip);
+        let client = reqwest::blocking::Client::new();
+        let release = || {
+            client
+                .delete(format!("{}/residents/1191993", ip))
+                .timeout(Duration::from_secs(2))
+                .send()
+                .expect("Failed to execute request")
+        };
+        // left over if an earlier run stopped half way
+        release();
+        // facility 18, card 12345 is stored as one number
+        let resident = json!({"rfid": "18,12345", "name": "Badge, Test", "doc": "918273", "room": "D-2", "unit": 4, "current_location": 4, "level": 2});
+        let created = client
+            .post(format!("{}/residents", ip))
+            .json(&resident)
+            .timeout(Duration::from_secs(2))
+            .send()
+            .expect("Failed to execute request")
+            .json::<Value>()
+            .unwrap();
+        assert_eq!(created["success"], true);
+        // and found again from the raw Wiegand-26 frame or with leading zeros
+        for raw in ["W26:246073", "0001191993"] {
+            let found = make_request(&format!("residents/{}", raw), reqwest::Method::GET, None)
+                .json::<Value>()
+                .unwrap();
+            assert_eq!(found["data"][0]["rfid"], "1191993");
+        }
+
+        let bad_parity = client
+            .post(format!("{}/timestamps", ip))
+            .json(&json!({"rfid": "W26:246053", "location": 6}))
+            .timeout(Duration::from_secs(2))
+            .send()
+            .expect("Failed to execute request")
+            .json::<Value>()
+            .unwrap();
+        assert_eq!(bad_parity["success"], false);
+        assert!(bad_parity["message"].as_str().unwrap().contains("parity"));
+        assert_eq!(release().status().as_u16(), 204);
+    }
+
+    #[test]
+    fn test_timestamps_post_wedge_hex() {
+        use mvscan_bridge::frames::{decode, Format};
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let ip = format!("http://{}:8080/api", ip);
+        let client = reqwest::blocking::Client::new();
+        // a card a wedge reader types with letters in it, new each run
+        let millis = chrono::Local::now().timestamp_millis();
+        let typed = format!("A{:X}", millis % 0x1000_0000);
+        let rfid = u64::from_str_radix(&typed, 16).unwrap().to_string();
+        let resident = json!({"rfid": rfid, "name": "Wedge, Test", "doc": format!("w{}", millis), "room": "D-3", "unit": 4, "current_location": 4, "level": 2});
+        let created = client
+            .post(format!("{}/residents", ip))
+            .json(&resident)
+            .timeout(Duration::from_secs(2))
+            .send()
+            .expect("Failed to execute request")
+            .json::<Value>()
+            .unwrap();
+        assert_eq!(created["success"], true);
+
+        // posted the way the bridge sends it
+        let scan = client
+            .post(format!("{}/timestamps", ip))
+            .json(&json!({"rfid": decode(Format::Wedge, &typed).unwrap(), "location": 6}))
+            .timeout(Duration::from_secs(2))
+            .send()
+            .expect("Failed to execute request");
+        assert_eq!(scan.status().as_u16(), 201);
+        let scan = scan.json::<Value>().unwrap();
+        assert_eq!(scan["data"][0]["resident"]["rfid"], rfid);
+        assert_eq!(scan["data"][0]["timestamp"]["location"], 6);
+    }
+
+    #[test]
+    fn test_mqtt_scan() {
+        use crate::broker::{Broker, Client};
+        // the server is started with MQTT_URL=localhost:18830 and MQTT_DEVICES=mqtt-test=8
+        let broker = Broker::start(18830);
+        assert!(broker.wait_for_subscriber("scanners/+/scan", Duration::from_secs(10)));
+
+        let mut device = Client::connect(18830, "mqtt-test");
+        device.subscribe("scanners/mqtt-test/ack");
+        let scan = r#"{"rfid": "7982377777777777", "id": "scan-1"}"#;
+        device.publish("scanners/mqtt-test/scan", scan);
+        let (topic, ack) = device.next_message(Duration::from_secs(5)).unwrap();
+        assert_eq!(topic, "scanners/mqtt-test/ack");
+        let ack: Value = serde_json::from_slice(&ack).unwrap();
+        assert_eq!(ack["success"], true);
+        assert_eq!(ack["id"], "scan-1");
+        assert_eq!(ack["data"][0]["timestamp"]["location"], 8);
+        assert_eq!(ack["data"][0]["timestamp"]["scanner"], "mqtt-test");
+
+        // a redelivery is acked with the original movement, not applied again
+        device.publish("scanners/mqtt-test/scan", scan);
+        let (_, again) = device.next_message(Duration::from_secs(5)).unwrap();
+        let again: Value = serde_json::from_slice(&again).unwrap();
+        assert_eq!(
+            again["data"][0]["timestamp"]["id"],
+            ack["data"][0]["timestamp"]["id"]
+        );
+    }
+
+    #[test]
+    fn test_webhooks_delivery() {
+        use crate::hook_stub::HookStub;
+        use hmac::{Hmac, Mac};
+        let stub = HookStub::start(18931);
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let api = format!("http://{}:8080/api", ip);
+        let client = reqwest::blocking::Client::new();
+        let scan = |location: &Value| -> Value {
+            client
+                .post(format!("{}/timestamps", api))
+                .json(&json!({"rfid": "333333339993333", "location": location}))
+                .send()
+                .unwrap()
+                .json()
+                .unwrap()
+        };
+        // Matthew Moore starts out in location 4, put him back if an earlier run left him away
+        let mut location = client
+            .get(format!("{}/residents/333333339993333", api))
+            .send()
+            .unwrap()
+            .json::<Value>()
+            .unwrap()["data"][0]["current_location"]
+            .clone();
+        if location == 0 {
+            location = scan(&json!(4))["data"][0]["timestamp"]["location"].clone();
+            assert_eq!(location, 4);
+        }
+        let created: Value = client
+            .post(format!("{}/webhooks", api))
+            .json(&json!({"url": "http://127.0.0.1:18931/hook", "events": ["movement", "away"], "secret": "s3cret"}))
+            .send()
+            .unwrap()
+            .json()
+            .unwrap();
+        assert_eq!(created["success"], true);
+        assert!(created["data"][0].get("secret").is_none());
+        let id = created["data"][0]["id"].as_i64().unwrap();
+
+        // scanning where he is takes him away
+        let scan = scan(&location);
+        assert_eq!(scan["data"][0]["timestamp"]["location"], 0);
+
+        let mut events = Vec::new();
+        while let Some(request) = stub.next_request(Duration::from_secs(10)) {
+            let mut mac = Hmac::<sha2::Sha256>::new_from_slice(b"s3cret").unwrap();
+            mac.update(&request.body);
+            let expected = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+            assert_eq!(request.headers["x-mvscan-signature"], expected);
+            let body: Value = serde_json::from_slice(&request.body).unwrap();
+            assert_eq!(
+                request.headers["x-mvscan-event"],
+                body["event"].as_str().unwrap()
+            );
+            assert_eq!(
+                body["data"]["timestamp"]["id"],
+                scan["data"][0]["timestamp"]["id"]
+            );
+            events.push(body["event"].as_str().unwrap().to_string());
+            if events.len() == 2 {
+                break;
+            }
+        }
+        events.sort();
+        assert_eq!(events, ["away", "movement"]);
+
+        // the worker records the outcome once the stub has answered
+        let delivered = (0..20).any(|_| {
+            std::thread::sleep(Duration::from_millis(100));
+            let log: Value = client
+                .get(format!("{}/webhooks/{}/deliveries", api, id))
+                .send()
+                .unwrap()
+                .json()
+                .unwrap();
+            let log = log["data"].as_array().unwrap().clone();
+            log.len() == 2
+                && log
+                    .iter()
+                    .all(|d| d["status"] == "delivered" && d["response_status"] == 200)
+        });
+        assert!(delivered);
+
+        let rejected: Value = client
+            .patch(format!("{}/webhooks/{}", api, id))
+            .json(&json!({"events": ["teleported"]}))
+            .send()
+            .unwrap()
+            .json()
+            .unwrap();
+        assert_eq!(rejected["success"], false);
+        client
+            .delete(format!("{}/webhooks/{}", api, id))
+            .send()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_rules_unit_count() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let api = format!("http://{}:8080/api", ip);
+        let client = reqwest::blocking::Client::new();
+        let post = |endpoint: &str, body: Value| -> Value {
+            client
+                .post(format!("{}/{}", api, endpoint))
+                .json(&body)
+                .send()
+                .unwrap()
+                .json()
+                .unwrap()
+        };
+        let rule = post(
+            "rules",
+            json!({"name": "Bravo in the conference room", "kind": "unit_count", "location": 16, "unit": 6, "max_count": 0}),
+        );
+        assert_eq!(rule["success"], true);
+        let rule_id = rule["data"][0]["id"].as_i64().unwrap();
+        let missing = post(
+            "rules",
+            json!({"name": "no minutes", "kind": "away_too_long"}),
+        );
+        assert_eq!(missing["success"], false);
+
+        // Michael Johnson is housed in unit 6, arriving makes one too many
+        let scan = json!({"rfid": "555555666555555", "location": 16});
+        let arrived = post("timestamps", scan.clone());
+        assert_eq!(arrived["data"][0]["timestamp"]["location"], 16);
+        let alert = arrived["data"][0]["alerts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|alert| alert["rule_id"] == rule_id)
+            .unwrap()
+            .clone();
+        assert_eq!(alert["kind"], "unit_count");
+        let alert_id = alert["id"].as_i64().unwrap();
+
+        let acked = post(
+            &format!("alerts/{}/acknowledge", alert_id),
+            json!({"by": "Sgt. Rivera"}),
+        );
+        assert_eq!(acked["data"][0]["acknowledged_by"], "Sgt. Rivera");
+        let again = post(
+            &format!("alerts/{}/acknowledge", alert_id),
+            json!({"by": "Sgt. Rivera"}),
+        );
+        assert_eq!(again["success"], false);
+
+        // leaving clears the rule, so its alert is resolved
+        let left = post("timestamps", scan);
+        assert_eq!(left["data"][0]["timestamp"]["location"], 0);
+        let resolved: Value = client
+            .get(format!("{}/alerts?rule={}&status=resolved", api, rule_id))
+            .send()
+            .unwrap()
+            .json()
+            .unwrap();
+        assert_eq!(resolved["data"][0]["id"], alert_id);
+        assert_eq!(resolved["data"][0]["resolved_by"], "system");
+        client
+            .delete(format!("{}/rules/{}", api, rule_id))
+            .send()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_residents_away() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let api = format!("http://{}:8080/api", ip);
+        let client = reqwest::blocking::Client::new();
+        let get = |endpoint: &str| -> Value {
+            client
+                .get(format!("{}/{}", api, endpoint))
+                .send()
+                .unwrap()
+                .json()
+                .unwrap()
+        };
+        // a resident of their own each run, so earlier runs' history and alerts don't count
+        let millis = chrono::Local::now().timestamp_millis();
+        let rfid = format!("46{}", millis);
+        let name = format!("Away, Test {}", millis);
+        let resident = json!({"rfid": rfid, "name": name, "doc": format!("a{}", millis), "room": "D-6", "unit": 4, "current_location": 8, "level": 2});
+        let created: Value = client
+            .post(format!("{}/residents", api))
+            .json(&resident)
+            .send()
+            .unwrap()
+            .json()
+            .unwrap();
+        assert_eq!(created["success"], true);
+        // they went to the library 50 minutes ago and scanned out of it 5 minutes later
+        let ago = |minutes: i64| {
+            (chrono::Local::now().naive_local() - chrono::Duration::minutes(minutes))
+                .format("%Y-%m-%dT%H:%M:%S")
+                .to_string()
+        };
+        let post = |scan: Value| -> Value {
+            client
+                .post(format!("{}/timestamps", api))
+                .json(&scan)
+                .send()
+                .unwrap()
+                .json()
+                .unwrap()
+        };
+        let library = post(json!({"rfid": rfid, "location": 24, "scanned_at": ago(50)}));
+        assert_eq!(library["data"][0]["timestamp"]["location"], 24);
+        let out = post(
+            json!({"rfid": rfid, "location": 24, "scanner": "library-door", "scanned_at": ago(45)}),
+        );
+        assert_eq!(out["data"][0]["timestamp"]["location"], 0);
+
+        let away = get("residents/away?min_minutes=40");
+        let entry = away["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|a| a["resident"]["rfid"] == rfid)
+            .unwrap()
+            .clone();
+        assert_eq!(entry["last_location"], 24);
+        assert_eq!(entry["last_location_name"], "LIBRARY");
+        assert_eq!(entry["scanned_out_by"], "library-door");
+        assert!(entry["minutes"].as_i64().unwrap() >= 45);
+
+        // the server is started with RULES_SWEEP_SECONDS=1, past the default 30 minute rule
+        let overdue = |status: &str| {
+            get(&format!("alerts?kind=overdue_pass&status={}", status))["data"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|a| a["message"].as_str().unwrap().contains(&name))
+                .cloned()
+        };
+        let alert = (0..50)
+            .find_map(|_| {
+                std::thread::sleep(Duration::from_millis(100));
+                overdue("open")
+            })
+            .unwrap();
+        assert!(alert["message"]
+            .as_str()
+            .unwrap()
+            .contains("30 minute limit"));
+
+        let back = post(json!({"rfid": rfid, "location": 8}));
+        assert_eq!(back["data"][0]["timestamp"]["location"], 8);
+        assert_eq!(overdue("resolved").unwrap()["id"], alert["id"]);
+    }
+
+    #[test]
+    fn test_residents_by_doc() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let api = format!("http://{}:8080/api/residents", ip);
+        let client = reqwest::blocking::Client::new();
+        let get = |path: &str| -> Value {
+            client
+                .get(format!("{}/{}", api, path))
+                .send()
+                .unwrap()
+                .json()
+                .unwrap()
+        };
+        let patch = |path: &str, body: Value| -> Value {
+            client
+                .patch(format!("{}/{}", api, path))
+                .json(&body)
+                .send()
+                .unwrap()
+                .json()
+                .unwrap()
+        };
+
+        let sarah = get("by-doc/543201")["data"][0].clone();
+        assert_eq!(sarah["name"], "Sarah Davis");
+        let id = sarah["id"].as_i64().unwrap();
+        assert_eq!(
+            get(&format!("by-id/{}", id))["data"][0]["rfid"],
+            "222222333222222"
+        );
+        assert_eq!(get("by-doc/000000")["message"], "Resident not found");
+
+        assert_eq!(
+            patch("by-doc/543201", json!({"room": "D-2b"}))["success"],
+            true
+        );
+        assert_eq!(get("222222333222222")["data"][0]["room"], "D-2b");
+        // John Doe's DOC number
+        let taken = patch(&format!("by-id/{}", id), json!({"doc": "123345"}));
+        assert_eq!(taken["success"], false);
+        assert_eq!(
+            taken["message"],
+            "DOC number 123345 already belongs to another resident"
+        );
+        patch(&format!("by-id/{}", id), json!({"room": "D-1b"}));
+    }
+
+    #[test]
+    fn test_residents_notes() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let api = format!("http://{}:8080/api", ip);
+        let client = reqwest::blocking::Client::new();
+        let send = |request: reqwest::blocking::RequestBuilder| -> Value {
+            request.send().unwrap().json().unwrap()
+        };
+        let in_an_hour = (chrono::Local::now().naive_local() + chrono::Duration::hours(1))
+            .format("%Y-%m-%dT%H:%M:%S")
+            .to_string();
+        let notes = format!("{}/residents/123455623562354/notes", api);
+        // an earlier run's notes are still on record
+        let count = |url: String| send(client.get(url))["data"].as_array().map_or(0, Vec::len);
+        let (active_before, all_before) = (
+            count(notes.clone()),
+            count(format!("{}?include_expired=true", notes)),
+        );
+
+        let hold = send(client.post(&notes).json(&json!({
+            "author": "Sgt. Reyes",
+            "category": "medical hold",
+            "note": "Insulin at 8pm, do not release to yard",
+            "severity": "high",
+            "expires_at": in_an_hour,
+        })));
+        assert_eq!(hold["success"], true);
+        let hold = hold["data"][0].clone();
+        let escort = send(client.post(&notes).json(&json!({
+            "author": "Sgt. Reyes",
+            "category": "escort required",
+            "note": "Escort to medical only",
+            "severity": "high",
+        })))["data"][0]
+            .clone();
+        send(client.post(&notes).json(&json!({
+            "author": "Ofc. Lin",
+            "category": "no recreation",
+            "note": "Loses gym for the week",
+            "severity": "low",
+        })));
+        assert_eq!(
+            send(
+                client
+                    .post(&notes)
+                    .json(&json!({"author": "", "category": "x", "note": "y"}))
+            )["success"],
+            false
+        );
+
+        // ending the escort note keeps it on record but off the kiosk
+        let ended = send(
+            client
+                .patch(format!("{}/notes/{}", api, escort["id"]))
+                .json(&json!({"expires_at": "2020-01-01T00:00:00"})),
+        );
+        assert_eq!(ended["data"][0]["category"], "escort required");
+        assert_eq!(count(notes.clone()), active_before + 2);
+        assert_eq!(
+            count(format!("{}?include_expired=true", notes)),
+            all_before + 3
+        );
+
+        let scan = send(
+            client
+                .post(format!("{}/timestamps", api))
+                .json(&json!({"rfid": "123455623562354", "location": 8})),
+        );
+        let flags = scan["data"][0]["flags"].as_array().unwrap();
+        assert_eq!(flags.len(), 1);
+        assert_eq!(flags[0]["id"], hold["id"]);
+        assert_eq!(flags[0]["category"], "medical hold");
+
+        // a full cell turns them away, the kiosk still shows the flags; no
+        // other test uses location 72
+        let cell = json!({"capacity": 0, "refuse_over_capacity": true});
+        client
+            .post(format!("{}/locations", api))
+            .json(&json!({"id": 72, "name": "Full Cell", "level": 1}))
+            .send()
+            .unwrap();
+        send(client.patch(format!("{}/locations/72", api)).json(&cell));
+        let refused = send(
+            client
+                .post(format!("{}/timestamps", api))
+                .json(&json!({"rfid": "123455623562354", "location": 72})),
+        );
+        assert_eq!(refused["success"], false);
+        assert_eq!(refused["data"][0]["flags"][0]["id"], hold["id"]);
+
+        let removed = send(client.delete(format!("{}/notes/{}", api, hold["id"])));
+        assert_eq!(removed["success"], true);
+        assert_eq!(
+            send(client.get(format!("{}/notes/{}", api, hold["id"])))["message"],
+            "Note not found"
+        );
+    }
+
+    #[test]
+    fn test_residents_search() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let search = |q: &str| -> Value {
+            reqwest::blocking::get(format!("http://{}:8080/api/residents/search?q={}", ip, q))
+                .unwrap()
+                .json()
+                .unwrap()
+        };
+        let names = |found: &Value| -> Vec<String> {
+            found["data"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|m| m["resident"]["name"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        // two residents no earlier run has, their name, room, DOC and badge carry the run's time
+        let millis = chrono::Local::now().timestamp_millis();
+        let tag: String = millis
+            .to_string()
+            .bytes()
+            .map(|d| (b'a' + d - b'0') as char)
+            .collect();
+        let client = reqwest::blocking::Client::new();
+        let seeded = [(1, "Ottoline", ""), (2, "Ambrose", "t")].map(|(n, first, room)| {
+            let resident = json!({"rfid": format!("47{}{}", millis, n), "name": format!("{} Fairweather{}", first, tag), "doc": format!("{}{}", millis, n), "room": format!("Q-{}{}", tag, room), "unit": 4, "current_location": 4, "level": 2});
+            let created: Value = client
+                .post(format!("http://{}:8080/api/residents", ip))
+                .json(&resident)
+                .send()
+                .unwrap()
+                .json()
+                .unwrap();
+            assert_eq!(created["success"], true);
+            resident
+        });
+        let ours = |found: &Value| -> Vec<Value> {
+            found["data"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter(|m| seeded.iter().any(|r| r["rfid"] == m["resident"]["rfid"]))
+                .cloned()
+                .collect()
+        };
+        let ottoline = seeded[0]["name"].as_str().unwrap().to_string();
+
+        // "Last, First" and a misspelling both find her, the exact name first
+        let exact = ours(&search(&format!("Fairweather{},%20Ottoline", tag)));
+        assert_eq!(exact[0]["resident"]["name"], ottoline);
+        assert_eq!(exact[0]["matched"], "name");
+        assert_eq!(exact[0]["score"], 1.0);
+        assert!(names(&search(&format!("Fairwether{}", tag))).contains(&ottoline));
+
+        let doc = ours(&search(&millis.to_string()));
+        assert_eq!(doc.len(), 2);
+        assert!(doc.iter().all(|m| m["matched"] == "doc"));
+
+        let badge = ours(&search(seeded[1]["rfid"].as_str().unwrap()));
+        assert_eq!(badge[0]["resident"]["rfid"], seeded[1]["rfid"]);
+        assert_eq!(badge[0]["matched"], "rfid");
+        assert_eq!(badge[0]["score"], 1.0);
+
+        // the room named exactly, whatever the case, before the one it's a prefix of
+        let room = ours(&search(&format!("q-{}", tag)));
+        assert_eq!(room[0]["resident"]["room"], seeded[0]["room"]);
+        assert_eq!(room[0]["matched"], "room");
+        assert_eq!(room[1]["resident"]["room"], seeded[1]["room"]);
+        assert_eq!(search("")["success"], false);
+    }
+
+    /// A `width` x `height` RGB gradient as a PNG
+    fn gradient_png(width: u32, height: u32) -> Vec<u8> {
+        use std::io::Write;
+        let chunk = |png: &mut Vec<u8>, kind: &[u8], data: &[u8]| {
+            png.extend((data.len() as u32).to_be_bytes());
+            let start = png.len();
+            png.extend(kind);
+            png.extend(data);
+            let crc = crc32fast::hash(&png[start..]);
+            png.extend(crc.to_be_bytes());
+        };
+        let mut pixels = Vec::new();
+        for y in 0..height {
+            pixels.push(0);
+            for x in 0..width {
+                pixels.extend([(x * 255 / width) as u8, (y * 255 / height) as u8, 128]);
+            }
+        }
+        let mut zlib = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        zlib.write_all(&pixels).unwrap();
+        let mut header = Vec::new();
+        header.extend(width.to_be_bytes());
+        header.extend(height.to_be_bytes());
+        header.extend([8, 2, 0, 0, 0]);
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        chunk(&mut png, b"IHDR", &header);
+        chunk(&mut png, b"IDAT", &zlib.finish().unwrap());
+        chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    #[test]
+    fn test_residents_photo() {
+        let ip = std::env::var("LOCAL_IP").unwrap_or("localhost".to_string());
+        let server = format!("http://{}:8080", ip);
+        let client = reqwest::blocking::Client::new();
+        let upload = |body: Vec<u8>| -> Value {
+            client
+                .put(format!("{}/api/residents/666688866666666/photo", server))
+                .body(body)
+                .send()
+                .unwrap()
+                .json()
+                .unwrap()
+        };
+        let fetch = |url: &Value| {
+            client
+                .get(format!("{}{}", server, url.as_str().unwrap()))
+                .send()
+                .unwrap()
+        };
+
+        let photo = gradient_png(320, 200);
+        let uploaded = upload(photo.clone());
+        assert_eq!(uploaded["success"], true);
+        let resident = &uploaded["data"][0];
+        assert_eq!(
+            fetch(&resident["photo_url"]).bytes().unwrap().to_vec(),
+            photo
+        );
+        let thumbnail = fetch(&resident["thumbnail_url"]);
+        assert_eq!(thumbnail.headers()["content-type"], "image/png");
+        // IHDR starts 16 bytes in: width then height
+        let thumbnail = thumbnail.bytes().unwrap();
+        let side = |at: usize| u32::from_be_bytes(thumbnail[at..at + 4].try_into().unwrap());
+        assert_eq!((side(16), side(20)), (160, 100));
+
+        // staff see the photo when the badge is scanned
+        let scan: Value = client
+            .post(format!("{}/api/timestamps", server))
+            .json(&json!({"rfid": "666688866666666", "location": 8}))
+            .send()
+            .unwrap()
+            .json()
+            .unwrap();
+        assert_eq!(
+            scan["data"][0]["resident"]["thumbnail_url"],
+            resident["thumbnail_url"]
+        );
+
+        let rejected = upload(b"not a picture".to_vec());
+        assert_eq!(rejected["success"], false);
+        assert_eq!(rejected["message"], "Photo must be a PNG or JPEG image");
+        assert_eq!(upload(gradient_png(32, 32))["success"], false);
+
+        // a new photo replaces the old files
+        let replaced = upload(gradient_png(100, 100));
+        assert_ne!(replaced["data"][0]["photo_url"], resident["photo_url"]);
+        assert_eq!(fetch(&resident["photo_url"]).status(), 404);
+
+        let removed: Value = client
+            .delete(format!("{}/api/residents/666688866666666/photo", server))
+            .send()
+            .unwrap()
+            .json()
+            .unwrap();
+        assert_eq!(removed["data"][0]["photo_url"], Value::Null);
+        assert_eq!(fetch(&replaced["data"][0]["thumbnail_url"]).status(), 404);
+    }
 }